@@ -6,13 +6,14 @@ extern crate petgraph;
 
 use conrod::{widget, Borderable, Colorable, Labelable, Positionable, Sizeable, Widget};
 use conrod::backend::glium::glium::{self, Surface};
-use conrod_graph_widget::{node, Event, EdgeEvent, Node, NodeEvent, NodeSocket, Graph};
+use conrod_graph_widget::{AnyNodeEvent, Event, EdgeEvent, Node, NodeEvent, Graph};
 use std::collections::HashMap;
 
 
 widget_ids! {
     struct Ids {
         graph,
+        box_selection,
     }
 }
 
@@ -148,26 +149,18 @@ fn set_widgets(ui: &mut conrod::UiCell, ids: &Ids, graph: &mut MyGraph, layout:
     //
     // The session is used in multiple stages:
     //
-    // 1. `Nodes` for setting a node widget for each node.
-    // 2. `Edges` for setting an edge widget for each edge.
-    // 3. `Final` for optionally displaying zoom percentage and cam position.
-
-    let session = {
-        // An identifier for each node in the graph.
-        let node_indices = graph.node_indices();
-        // Describe each edge in the graph as NodeSocket -> NodeSocket.
-        let edges = graph.raw_edges()
-            .iter()
-            .map(|e| {
-                let start = NodeSocket { id: e.source(), socket_index: e.weight.0 };
-                let end = NodeSocket { id: e.target(), socket_index: e.weight.1 };
-                (start, end)
-            });
-        Graph::new(node_indices, edges, layout)
-            .wh_of(ui.window)
-            .middle_of(ui.window)
-            .set(ids.graph, ui)
-    };
+    // 1. `Events` for handling the events that have occurred since the last update.
+    // 2. `Nodes` for setting a node widget for each node.
+    // 3. `Edges` for setting an edge widget for each edge.
+    // 4. `Annotations` for setting comment boxes behind the nodes and edges.
+    // 5. `Final` for optionally displaying zoom percentage and cam position.
+
+    // Each edge's `(usize, usize)` weight describes its output and input socket indices.
+    let session = Graph::from_graph(graph, layout)
+        .prune_layout(true)
+        .wh_of(ui.window)
+        .middle_of(ui.window)
+        .set(ids.graph, ui);
 
     //////////////////
     ///// EVENTS /////
@@ -182,8 +175,25 @@ fn set_widgets(ui: &mut conrod::UiCell, ids: &Ids, graph: &mut MyGraph, layout:
                 // },
                 NodeEvent::Remove(node_id) => {
                 },
+                NodeEvent::Removed(node_id) => {
+                    layout.remove(node_id);
+                },
+                NodeEvent::DragStarted { node_id, from } => {
+                },
                 NodeEvent::Dragged { node_id, to, .. } => {
-                    *layout.get_mut(&node_id).unwrap() = to;
+                    *layout.get_mut(node_id).unwrap() = to;
+                },
+                NodeEvent::DragEnded { node_id, to } => {
+                },
+                NodeEvent::Resized { node_id, to, .. } => {
+                },
+                NodeEvent::CollapseToggled { node_id, collapsed } => {
+                },
+                NodeEvent::DoubleClicked(node_id) => {
+                },
+                NodeEvent::Hovered(node_id) => {
+                },
+                NodeEvent::Unhovered(node_id) => {
                 },
             },
             Event::Edge(event) => match event {
@@ -195,6 +205,20 @@ fn set_widgets(ui: &mut conrod::UiCell, ids: &Ids, graph: &mut MyGraph, layout:
                 },
                 EdgeEvent::Remove { start, end } => {
                 },
+                EdgeEvent::Reconnect { old_start, old_end, new_end } => {
+                },
+                EdgeEvent::SplitRequested { node_id, start, end } => {
+                },
+                EdgeEvent::SocketHovered { socket_type, socket } => {
+                },
+                EdgeEvent::SocketUnhovered { socket_type, socket } => {
+                },
+                EdgeEvent::TargetHovered { start, end } => {
+                },
+            },
+            Event::ContextMenu { at, target } => {
+            },
+            Event::BackgroundClicked(point) => {
             },
         }
     }
@@ -220,16 +244,45 @@ fn set_widgets(ui: &mut conrod::UiCell, ids: &Ids, graph: &mut MyGraph, layout:
         let node_id = node.node_id();
         let inputs = graph.neighbors_directed(node_id, petgraph::Incoming).count();
         let outputs = graph.neighbors_directed(node_id, petgraph::Outgoing).count();
-        let button = widget::Button::new()
-            .label(&graph[node_id])
-            .border(0.0);
-        let widget = Node::new(button)
-            .inputs(inputs)
-            .outputs(outputs)
-            .socket_color(conrod::color::LIGHT_RED)
-            .w_h(100.0, 60.0);
-        for _click in node.widget(widget).set(ui).widget_event {
-            println!("{} was clicked!", &graph[node_id]);
+        //
+        // Nodes without outputs are drawn as toggles rather than buttons. `widget_a` and
+        // `widget_b` allow for choosing between the two widget types within a single `match`.
+        let node = match outputs {
+            0 => {
+                let toggle = widget::Toggle::new(false)
+                    .label(&graph[node_id])
+                    .border(0.0);
+                let widget = Node::new(toggle)
+                    .inputs(inputs)
+                    .outputs(outputs)
+                    .socket_color(conrod::color::LIGHT_BLUE)
+                    .w_h(100.0, 60.0);
+                node.widget_b(widget)
+            },
+            _ => {
+                let button = widget::Button::new()
+                    .label(&graph[node_id])
+                    .border(0.0);
+                let widget = Node::new(button)
+                    .inputs(inputs)
+                    .outputs(outputs)
+                    .socket_color(conrod::color::LIGHT_RED)
+                    .w_h(100.0, 60.0);
+                node.widget_a(widget)
+            },
+        };
+        match node.set(ui) {
+            AnyNodeEvent::A(event) => {
+                for _click in event.widget_event.into_iter().flat_map(|c| c) {
+                    println!("{} was clicked!", &graph[node_id]);
+                }
+            },
+            AnyNodeEvent::B(event) => {
+                for is_on in event.widget_event.into_iter().flat_map(|t| t) {
+                    let state = if is_on { "on" } else { "off" };
+                    println!("{} was toggled {}!", &graph[node_id], state);
+                }
+            },
         }
     }
 
@@ -241,16 +294,23 @@ fn set_widgets(ui: &mut conrod::UiCell, ids: &Ids, graph: &mut MyGraph, layout:
 
     let mut session = session.next();
     for edge in session.edges() {
-        let (a, b) = node::edge_socket_rects(&edge, ui);
-        let line = widget::Line::abs(a.xy(), b.xy())
-            .color(conrod::color::DARK_CHARCOAL)
-            .thickness(3.0);
-
         // Each edge contains:
         //
         // `start` - The unique node identifier for the node at the start of the edge with point.
         // `end` - The unique node identifier for the node at the end of the edge with point.
         // `widget_id` - The wiget identifier for this edge.
-        edge.widget(line).set(ui);
+        edge.straight_line(ui).set(ui);
+    }
+
+    // Draw the in-progress box selection if there is one.
+    if let Some(rect) = session.box_selection_rect() {
+        let camera = session.camera().point();
+        widget::BorderedRectangle::new(rect.dim())
+            .xy_relative_to(ids.graph, [rect.x() - camera[0], rect.y() - camera[1]])
+            .color(conrod::color::LIGHT_BLUE.alpha(0.2))
+            .border(1.0)
+            .border_color(conrod::color::LIGHT_BLUE)
+            .parent(ids.graph)
+            .set(ids.box_selection, ui);
     }
 }
@@ -0,0 +1,202 @@
+//! A free-floating, labelled rectangle for documenting regions of a graph.
+//!
+//! Annotations are not part of the graph's nodes or edges. They are instantiated during the
+//! **SessionAnnotations** stage and are drawn behind the nodes.
+
+use conrod::{color, widget, Borderable, Color, Colorable, Dimensions, FontSize, Positionable};
+use conrod::{Scalar, Widget};
+use conrod::position::Rect;
+
+/// The width and height of the handle in the bottom right corner used to resize an annotation.
+pub const RESIZE_HANDLE_SIZE: Scalar = 12.0;
+/// The smallest width and height to which an annotation may be resized.
+pub const MIN_DIMENSION: Scalar = RESIZE_HANDLE_SIZE * 2.0;
+/// The depth at which annotations are instantiated relative to the nodes and edges.
+///
+/// Widgets of greater depth are drawn behind their siblings.
+pub const DEPTH: Scalar = 1.0;
+
+/// A labelled rectangle that may be dragged and resized.
+#[derive(Clone, Debug, WidgetCommon)]
+pub struct Annotation<'a> {
+    /// Data necessary and common for all widget builder types.
+    #[conrod(common_builder)]
+    pub common: widget::CommonBuilder,
+    /// Unique styling for the **Annotation**.
+    pub style: Style,
+    /// The text displayed in the top left corner of the annotation.
+    pub label: &'a str,
+    /// Whether or not dragging the annotation also drags the nodes that lie within it.
+    pub move_contained_nodes: bool,
+}
+
+/// Unique styling for the **Annotation** widget.
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    /// Color of the annotation's surface.
+    #[conrod(default = "color::rgba(1.0, 1.0, 1.0, 0.05)")]
+    pub color: Option<Color>,
+    /// Thickness of the annotation's border.
+    #[conrod(default = "1.0")]
+    pub border: Option<Scalar>,
+    /// Color of the annotation's border.
+    #[conrod(default = "color::rgba(1.0, 1.0, 1.0, 0.2)")]
+    pub border_color: Option<Color>,
+    /// Color of the annotation's label.
+    #[conrod(default = "color::WHITE")]
+    pub label_color: Option<Color>,
+    /// Font size of the annotation's label.
+    #[conrod(default = "14")]
+    pub label_font_size: Option<FontSize>,
+}
+
+widget_ids! {
+    struct Ids {
+        rectangle,
+        resize_handle,
+        label,
+    }
+}
+
+/// Unique state for the `Annotation`.
+pub struct State {
+    ids: Ids,
+}
+
+/// Interactions with the **Annotation** since the last update.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The annotation was dragged by the given distance.
+    Moved([Scalar; 2]),
+    /// The annotation's resize handle was dragged, requesting the given dimensions.
+    ///
+    /// The top left corner of the annotation is expected to remain in place.
+    Resized(Dimensions),
+}
+
+impl<'a> Annotation<'a> {
+    /// Begin building a new **Annotation** with the given label.
+    pub fn new(label: &'a str) -> Self {
+        Annotation {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            label,
+            move_contained_nodes: false,
+        }
+    }
+
+    /// Whether or not dragging the annotation also drags the nodes whose positions lie within it.
+    ///
+    /// Only applies to annotations instantiated via `SessionAnnotations::set`.
+    pub fn move_contained_nodes(mut self, move_nodes: bool) -> Self {
+        self.move_contained_nodes = move_nodes;
+        self
+    }
+
+    /// Specify the color of the annotation's surface.
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    /// Specify the thickness of the annotation's border.
+    pub fn border(mut self, border: Scalar) -> Self {
+        self.style.border = Some(border);
+        self
+    }
+
+    /// Specify the color of the annotation's border.
+    pub fn border_color(mut self, color: Color) -> Self {
+        self.style.border_color = Some(color);
+        self
+    }
+
+    /// Specify the color of the annotation's label.
+    pub fn label_color(mut self, color: Color) -> Self {
+        self.style.label_color = Some(color);
+        self
+    }
+
+    /// Specify the font size of the annotation's label.
+    pub fn label_font_size(mut self, font_size: FontSize) -> Self {
+        self.style.label_font_size = Some(font_size);
+        self
+    }
+}
+
+impl<'a> Widget for Annotation<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = Option<Event>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State { ids: Ids::new(id_gen) }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style.clone()
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
+        let Annotation { label, .. } = self;
+
+        // The resize handle relative to the centre of the annotation.
+        let handle_rect = {
+            let half = RESIZE_HANDLE_SIZE / 2.0;
+            let xy = [rect.w() / 2.0 - half, -rect.h() / 2.0 + half];
+            Rect::from_xy_dim(xy, [RESIZE_HANDLE_SIZE; 2])
+        };
+
+        // Drags beginning over the resize handle resize the annotation, all others move it.
+        let event = ui.widget_input(id)
+            .drags()
+            .left()
+            .fold(None, |event, drag| {
+                let [dx, dy] = drag.delta_xy;
+                match event {
+                    None if handle_rect.is_over(drag.origin) => {
+                        Some(Event::Resized([rect.w() + dx, rect.h() - dy]))
+                    },
+                    None => Some(Event::Moved([dx, dy])),
+                    Some(Event::Resized([w, h])) => Some(Event::Resized([w + dx, h - dy])),
+                    Some(Event::Moved([x, y])) => Some(Event::Moved([x + dx, y + dy])),
+                }
+            })
+            .map(|event| match event {
+                Event::Resized([w, h]) => {
+                    Event::Resized([w.max(MIN_DIMENSION), h.max(MIN_DIMENSION)])
+                },
+                event => event,
+            });
+
+        let color = style.color(&ui.theme);
+        let border = style.border(&ui.theme);
+        let border_color = style.border_color(&ui.theme);
+        widget::BorderedRectangle::new(rect.dim())
+            .middle_of(id)
+            .graphics_for(id)
+            .color(color)
+            .border(border)
+            .border_color(border_color)
+            .set(state.ids.rectangle, ui);
+
+        widget::Rectangle::fill([RESIZE_HANDLE_SIZE; 2])
+            .bottom_right_of(id)
+            .graphics_for(id)
+            .color(border_color)
+            .set(state.ids.resize_handle, ui);
+
+        let label_color = style.label_color(&ui.theme);
+        let label_font_size = style.label_font_size(&ui.theme);
+        let pad = border + RESIZE_HANDLE_SIZE / 2.0;
+        widget::Text::new(label)
+            .top_left_with_margin_on(id, pad)
+            .graphics_for(id)
+            .color(label_color)
+            .font_size(label_font_size)
+            .set(state.ids.label, ui);
+
+        event
+    }
+}
@@ -0,0 +1,143 @@
+//! Graph data mutated explicitly rather than yielded by iterators each update.
+//!
+//! By default the **Graph** widget compares the nodes and edges yielded by its iterators against
+//! those of the previous update, which costs time proportional to the size of the graph even when
+//! nothing has changed. A `GraphData` records a revision that is bumped by each mutation, allowing
+//! a **Graph** built via `Graph::from_data` to skip the comparison entirely while the revision is
+//! unchanged.
+
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use super::{NodeId, NodeSocket};
+
+// Used to assign a unique identifier to each `GraphData` so that the **Graph** can tell them apart.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The nodes and edges of a graph, mutated explicitly via `add_node`, `remove_node`, `add_edge`
+/// and `remove_edge`.
+#[derive(Debug)]
+pub struct GraphData<NI: NodeId> {
+    // Uniquely identifies this `GraphData` among all others.
+    id: usize,
+    // Bumped whenever the nodes or edges change.
+    revision: u64,
+    // The nodes in the order in which they were added.
+    nodes: Vec<NI>,
+    // The same nodes as `nodes`, for fast membership tests.
+    node_set: HashSet<NI>,
+    // The edges in the order in which they were added.
+    edges: Vec<(NodeSocket<NI>, NodeSocket<NI>)>,
+}
+
+impl<NI> GraphData<NI>
+where
+    NI: NodeId,
+{
+    /// An empty graph.
+    pub fn new() -> Self {
+        GraphData {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            revision: 0,
+            nodes: Vec::new(),
+            node_set: HashSet::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add the given node.
+    ///
+    /// Returns `false` and leaves the graph unchanged if the node already exists.
+    pub fn add_node(&mut self, node_id: NI) -> bool {
+        if !self.node_set.insert(node_id) {
+            return false;
+        }
+        self.nodes.push(node_id);
+        self.revision += 1;
+        true
+    }
+
+    /// Remove the given node along with all edges that start or end at it.
+    ///
+    /// Returns `false` if the node does not exist.
+    pub fn remove_node(&mut self, node_id: NI) -> bool {
+        if !self.node_set.remove(&node_id) {
+            return false;
+        }
+        self.nodes.retain(|&id| id != node_id);
+        self.edges.retain(|&(start, end)| start.id != node_id && end.id != node_id);
+        self.revision += 1;
+        true
+    }
+
+    /// Add an edge from the `start` (output) socket to the `end` (input) socket.
+    ///
+    /// Duplicate edges are permitted, as with the edges yielded to `Graph::new`.
+    pub fn add_edge(&mut self, start: NodeSocket<NI>, end: NodeSocket<NI>) {
+        self.edges.push((start, end));
+        self.revision += 1;
+    }
+
+    /// Remove the most recently added edge from `start` to `end`.
+    ///
+    /// Returns `false` if there is no such edge.
+    pub fn remove_edge(&mut self, start: NodeSocket<NI>, end: NodeSocket<NI>) -> bool {
+        match self.edges.iter().rposition(|&edge| edge == (start, end)) {
+            None => false,
+            Some(index) => {
+                self.edges.remove(index);
+                self.revision += 1;
+                true
+            },
+        }
+    }
+
+    /// Whether or not the graph contains the given node.
+    pub fn contains_node(&self, node_id: NI) -> bool {
+        self.node_set.contains(&node_id)
+    }
+
+    /// All nodes in the order in which they were added.
+    pub fn nodes(&self) -> &[NI] {
+        &self.nodes
+    }
+
+    /// All edges in the order in which they were added.
+    pub fn edges(&self) -> &[(NodeSocket<NI>, NodeSocket<NI>)] {
+        &self.edges
+    }
+
+    /// The number of mutations made to the graph.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // The identity of this graph's current state, as compared by the **Graph** widget.
+    pub(crate) fn key(&self) -> (usize, u64) {
+        (self.id, self.revision)
+    }
+}
+
+impl<NI> Clone for GraphData<NI>
+where
+    NI: NodeId,
+{
+    // Clones are assigned their own identifier as they may be mutated independently.
+    fn clone(&self) -> Self {
+        GraphData {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            revision: self.revision,
+            nodes: self.nodes.clone(),
+            node_set: self.node_set.clone(),
+            edges: self.edges.clone(),
+        }
+    }
+}
+
+impl<NI> Default for GraphData<NI>
+where
+    NI: NodeId,
+{
+    fn default() -> Self {
+        GraphData::new()
+    }
+}
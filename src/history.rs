@@ -0,0 +1,189 @@
+//! An undo/redo history of the changes made to a graph via its **Graph** widget.
+//!
+//! The `GraphHistory` lives outside of the widget's state. The user feeds it the events that they
+//! have applied to their graph and `Layout` and it produces the changes required to undo or redo
+//! them.
+
+use conrod::Point;
+use super::{EdgeEvent, Event, NodeEvent, NodeId, NodeSocket};
+
+/// A single change made to a graph or its layout.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Change<NI> {
+    /// The node was moved from one position to another.
+    Move {
+        node_id: NI,
+        from: Point,
+        to: Point,
+    },
+    /// An edge was added between the two sockets.
+    AddEdge {
+        start: NodeSocket<NI>,
+        end: NodeSocket<NI>,
+    },
+    /// The edge between the two sockets was removed.
+    RemoveEdge {
+        start: NodeSocket<NI>,
+        end: NodeSocket<NI>,
+    },
+}
+
+/// A history of changes that may be undone and redone.
+///
+/// Each call to `record` outside of a group produces its own undo step. Changes recorded between
+/// `begin_group` and `end_group` are undone and redone together as a single step, and consecutive
+/// moves of the same node within a group are coalesced into one. This allows for a continuous drag
+/// (which produces a `Dragged` event every update) to be undone in a single step by beginning a
+/// group when the drag starts and ending it when the drag ends.
+#[derive(Clone, Debug)]
+pub struct GraphHistory<NI> {
+    // The steps that may be undone, most recent last.
+    undo: Vec<Vec<Change<NI>>>,
+    // The steps that may be redone, most recently undone last.
+    redo: Vec<Vec<Change<NI>>>,
+    // The step currently being grouped, if any.
+    group: Option<Vec<Change<NI>>>,
+}
+
+impl<NI> Change<NI>
+where
+    NI: NodeId,
+{
+    /// The change that reverses this one.
+    pub fn inverse(&self) -> Self {
+        match *self {
+            Change::Move { node_id, from, to } => Change::Move { node_id, from: to, to: from },
+            Change::AddEdge { start, end } => Change::RemoveEdge { start, end },
+            Change::RemoveEdge { start, end } => Change::AddEdge { start, end },
+        }
+    }
+}
+
+impl<NI> GraphHistory<NI>
+where
+    NI: NodeId,
+{
+    /// An empty history.
+    pub fn new() -> Self {
+        GraphHistory { undo: Vec::new(), redo: Vec::new(), group: None }
+    }
+
+    /// Record the given change.
+    ///
+    /// Recording a change clears the redo stack.
+    pub fn record(&mut self, change: Change<NI>) {
+        self.redo.clear();
+        match self.group {
+            None => self.undo.push(vec![change]),
+            Some(ref mut group) => {
+                // Coalesce consecutive moves of the same node.
+                if let (Change::Move { node_id, to, .. }, Some(last)) = (change, group.last_mut()) {
+                    if let Change::Move { node_id: last_id, to: ref mut last_to, .. } = *last {
+                        if last_id == node_id {
+                            *last_to = to;
+                            return;
+                        }
+                    }
+                }
+                group.push(change);
+            },
+        }
+    }
+
+    /// Record the changes described by the given event.
+    ///
+    /// `Dragged` events are recorded as moves, `Add` and `Remove` edge events as added and removed
+    /// edges and `Reconnect` events as the removal of the old edge followed by the addition of the
+    /// new one. `DragStarted` and `DragEnded` events begin and end a group respectively, so that
+    /// each drag gesture is undone in a single step. All other events are ignored.
+    ///
+    /// As `Add`, `Remove` and `Reconnect` events describe the user's attempts, only those events
+    /// that have actually been applied to the graph should be recorded.
+    pub fn record_event(&mut self, event: &Event<NI>) {
+        match *event {
+            Event::Node(NodeEvent::DragStarted { .. }) => self.begin_group(),
+            Event::Node(NodeEvent::Dragged { node_id, from, to }) => {
+                self.record(Change::Move { node_id, from, to });
+            },
+            Event::Node(NodeEvent::DragEnded { .. }) => self.end_group(),
+            Event::Edge(EdgeEvent::Add { start, end }) => {
+                self.record(Change::AddEdge { start, end });
+            },
+            Event::Edge(EdgeEvent::Remove { start, end }) => {
+                self.record(Change::RemoveEdge { start, end });
+            },
+            Event::Edge(EdgeEvent::Reconnect { old_start, old_end, new_end }) => {
+                let was_grouped = self.group.is_some();
+                self.begin_group();
+                self.record(Change::RemoveEdge { start: old_start, end: old_end });
+                self.record(Change::AddEdge { start: old_start, end: new_end });
+                if !was_grouped {
+                    self.end_group();
+                }
+            },
+            _ => (),
+        }
+    }
+
+    /// Begin grouping all recorded changes into a single undo step.
+    ///
+    /// Does nothing if a group has already begun.
+    pub fn begin_group(&mut self) {
+        if self.group.is_none() {
+            self.group = Some(Vec::new());
+        }
+    }
+
+    /// End the current group, pushing its changes as a single undo step.
+    ///
+    /// Empty groups are discarded.
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take() {
+            if !group.is_empty() {
+                self.undo.push(group);
+            }
+        }
+    }
+
+    /// Whether or not there is a step that may be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Whether or not there is a step that may be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Undo the most recent step.
+    ///
+    /// Returns the changes that the user should apply to their graph and `Layout` in order to undo
+    /// the step, or `None` if there is nothing to undo. Ends the current group, if any.
+    pub fn undo(&mut self) -> Option<Vec<Change<NI>>> {
+        self.end_group();
+        self.undo.pop().map(|step| {
+            let inverse = step.iter().rev().map(Change::inverse).collect();
+            self.redo.push(step);
+            inverse
+        })
+    }
+
+    /// Redo the most recently undone step.
+    ///
+    /// Returns the changes that the user should apply to their graph and `Layout` in order to redo
+    /// the step, or `None` if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<Vec<Change<NI>>> {
+        self.redo.pop().map(|step| {
+            let changes = step.clone();
+            self.undo.push(step);
+            changes
+        })
+    }
+
+    /// Forget all recorded changes.
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.group = None;
+    }
+}
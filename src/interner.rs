@@ -0,0 +1,105 @@
+//! Support for node identifiers that are not `Copy`, e.g. human-readable `String`s.
+//!
+//! The **Graph** widget copies node identifiers freely between its shared state, its iterators
+//! and its events, so the `NodeId` trait requires `Copy`. Rather than threading clones of each
+//! identifier through every update, an `Interner` assigns a `Copy` `InternedId` to each of the
+//! user's keys. The `InternedId`s are used as the graph's node identifiers while the `Interner`
+//! translates them back to keys when handling events.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A `Copy` node identifier assigned to a key by an `Interner`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InternedId(usize);
+
+/// A two-way map between the user's node keys and the `InternedId`s used to identify them within
+/// the graph.
+///
+/// Each key is assigned a unique `InternedId` the first time it is interned. `InternedId`s are
+/// never reused, so an identifier for a removed key will never refer to another key.
+#[derive(Clone, Debug)]
+pub struct Interner<K> {
+    // The key for each `InternedId`, indexed by the id. `None` for removed keys.
+    keys: Vec<Option<K>>,
+    // The `InternedId` assigned to each key.
+    ids: HashMap<K, InternedId>,
+}
+
+impl InternedId {
+    /// The index of the identifier in the order in which keys were interned.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+impl<K> Interner<K>
+where
+    K: Clone + Eq + Hash,
+{
+    /// An empty interner.
+    pub fn new() -> Self {
+        Interner { keys: Vec::new(), ids: HashMap::new() }
+    }
+
+    /// The `InternedId` for the given key, assigning a new one if the key has not yet been
+    /// interned.
+    pub fn intern(&mut self, key: &K) -> InternedId {
+        if let Some(&id) = self.ids.get(key) {
+            return id;
+        }
+        let id = InternedId(self.keys.len());
+        self.keys.push(Some(key.clone()));
+        self.ids.insert(key.clone(), id);
+        id
+    }
+
+    /// The `InternedId` for the given key if it has been interned.
+    pub fn id(&self, key: &K) -> Option<InternedId> {
+        self.ids.get(key).cloned()
+    }
+
+    /// The key to which the given `InternedId` was assigned.
+    ///
+    /// Returns `None` if the key has since been removed or if the id was produced by another
+    /// `Interner`.
+    pub fn key(&self, id: InternedId) -> Option<&K> {
+        self.keys.get(id.0).and_then(|key| key.as_ref())
+    }
+
+    /// Remove the given key, returning the `InternedId` to which it was assigned.
+    ///
+    /// Interning the key again assigns it a new `InternedId`.
+    pub fn remove(&mut self, key: &K) -> Option<InternedId> {
+        let id = match self.ids.remove(key) {
+            Some(id) => id,
+            None => return None,
+        };
+        self.keys[id.0] = None;
+        Some(id)
+    }
+
+    /// The number of interned keys.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Whether or not no keys are interned.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// An iterator yielding each interned key along with its `InternedId`.
+    pub fn iter(&self) -> ::std::collections::hash_map::Iter<K, InternedId> {
+        self.ids.iter()
+    }
+}
+
+impl<K> Default for Interner<K>
+where
+    K: Clone + Eq + Hash,
+{
+    fn default() -> Self {
+        Interner::new()
+    }
+}
@@ -0,0 +1,458 @@
+//! Functions for automatically generating a `Layout` for a graph.
+
+use conrod::{Point, Scalar};
+use conrod::position::Axis;
+use std::collections::HashMap;
+use super::{Layout, NodeId, NodeSocket, SocketSide};
+
+/// The ideal distance between two connected nodes within a force-directed layout.
+pub const FORCE_DIRECTED_IDEAL_DISTANCE: Scalar = 150.0;
+/// The distance between each layer within a layered layout.
+pub const LAYERED_LAYER_SPACING: Scalar = 150.0;
+/// The distance between each node within the same layer of a layered layout.
+pub const LAYERED_NODE_SPACING: Scalar = 150.0;
+
+impl<NI> Layout<NI>
+where
+    NI: NodeId,
+{
+    /// Produce a layout for the given nodes and edges using a simple Fruchterman-Reingold
+    /// force-directed simulation.
+    ///
+    /// Connected nodes are attracted to one another while all nodes repel each other. The
+    /// simulation runs for exactly `iterations` steps, cooling as it goes, so it always terminates
+    /// even for disconnected components. A weak pull toward the origin keeps disconnected
+    /// components from drifting apart indefinitely.
+    ///
+    /// Initial positions are produced from the given `seed`, so the same nodes, edges, iterations
+    /// and seed will always produce the same layout.
+    ///
+    /// Edges referring to nodes that are not yielded by `nodes` are ignored.
+    ///
+    /// The resulting positions are centered on the origin.
+    pub fn force_directed<N, E>(nodes: N, edges: E, iterations: usize, seed: u64) -> Self
+    where
+        N: IntoIterator<Item=NI>,
+        E: IntoIterator<Item=(NodeSocket<NI>, NodeSocket<NI>)>,
+    {
+        let (node_ids, indices) = node_indices(nodes);
+        let n = node_ids.len();
+        if n == 0 {
+            return Layout { map: HashMap::new() };
+        }
+
+        // Convert the edges to pairs of indices into `node_ids`, skipping self-loops.
+        let edges: Vec<(usize, usize)> = edges
+            .into_iter()
+            .filter_map(|(start, end)| {
+                let a = match indices.get(&start.id) { Some(&a) => a, None => return None };
+                let b = match indices.get(&end.id) { Some(&b) => b, None => return None };
+                if a == b { None } else { Some((a, b)) }
+            })
+            .collect();
+
+        // Scatter the nodes randomly within a square large enough to fit them all.
+        let k = FORCE_DIRECTED_IDEAL_DISTANCE;
+        let side = k * (n as Scalar).sqrt();
+        let mut rng = XorShift::new(seed);
+        let mut points: Vec<Point> = (0..n)
+            .map(|_| [(rng.next_scalar() - 0.5) * side, (rng.next_scalar() - 0.5) * side])
+            .collect();
+        let mut displacements = vec![[0.0; 2]; n];
+
+        let initial_temperature = side / 10.0;
+        for iteration in 0..iterations {
+            for d in displacements.iter_mut() {
+                *d = [0.0, 0.0];
+            }
+
+            // Every pair of nodes repel one another.
+            for a in 0..n {
+                for b in a + 1..n {
+                    let mut delta = [points[a][0] - points[b][0], points[a][1] - points[b][1]];
+                    let mut distance = magnitude(delta);
+                    // Nudge coincident nodes apart in some arbitrary, deterministic direction.
+                    if distance < ::std::f64::EPSILON {
+                        delta = [rng.next_scalar() - 0.5, rng.next_scalar() - 0.5];
+                        distance = magnitude(delta).max(::std::f64::EPSILON);
+                    }
+                    let force = k * k / distance;
+                    let fx = delta[0] / distance * force;
+                    let fy = delta[1] / distance * force;
+                    displacements[a][0] += fx;
+                    displacements[a][1] += fy;
+                    displacements[b][0] -= fx;
+                    displacements[b][1] -= fy;
+                }
+            }
+
+            // Connected nodes attract one another.
+            for &(a, b) in &edges {
+                let delta = [points[a][0] - points[b][0], points[a][1] - points[b][1]];
+                let distance = magnitude(delta);
+                if distance < ::std::f64::EPSILON {
+                    continue;
+                }
+                let force = distance * distance / k;
+                let fx = delta[0] / distance * force;
+                let fy = delta[1] / distance * force;
+                displacements[a][0] -= fx;
+                displacements[a][1] -= fy;
+                displacements[b][0] += fx;
+                displacements[b][1] += fy;
+            }
+
+            // Move each node along its displacement, limited by the current temperature.
+            let temperature = initial_temperature * (1.0 - iteration as Scalar / iterations as Scalar);
+            for (point, d) in points.iter_mut().zip(&displacements) {
+                // A weak pull toward the origin.
+                let distance_from_origin = magnitude(*point);
+                let gravity = [
+                    -point[0] * distance_from_origin / (k * 10.0),
+                    -point[1] * distance_from_origin / (k * 10.0),
+                ];
+                let d = [d[0] + gravity[0], d[1] + gravity[1]];
+                let length = magnitude(d);
+                if length < ::std::f64::EPSILON {
+                    continue;
+                }
+                let step = length.min(temperature);
+                point[0] += d[0] / length * step;
+                point[1] += d[1] / length * step;
+            }
+        }
+
+        centered_layout(node_ids, points)
+    }
+
+    /// Produce a layout with all nodes placed evenly around a circle of the given radius centered
+    /// on the origin.
+    ///
+    /// The node at index `i` of `n` nodes is placed at the angle `2π * i / n`, where nodes are
+    /// indexed in the order in which they are yielded by `nodes`. The resulting layout is
+    /// therefore reproducible for the same node ordering.
+    pub fn circular<N>(nodes: N, radius: Scalar) -> Self
+    where
+        N: IntoIterator<Item=NI>,
+    {
+        let (node_ids, _) = node_indices(nodes);
+        let n = node_ids.len();
+        let map = node_ids
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| {
+                let angle = 2.0 * ::std::f64::consts::PI * i as Scalar / n as Scalar;
+                (id, [radius * angle.cos(), radius * angle.sin()])
+            })
+            .collect();
+        Layout { map }
+    }
+
+    /// Produce a layered layout for the given nodes and edges, suitable for directed acyclic
+    /// graphs.
+    ///
+    /// Each node is assigned to a layer via longest-path ranking, so that every edge points from
+    /// a lower layer to a higher one. Nodes within each layer are spread evenly across the layer
+    /// in the order in which they are yielded by `nodes`.
+    ///
+    /// `direction` describes the side toward which the layers grow, e.g. `SocketSide::Bottom`
+    /// places the first layer at the top with each following layer beneath it.
+    ///
+    /// Cycles are broken deterministically by ignoring the edges that close them when visiting
+    /// nodes in the order in which they are yielded by `nodes`. Edges referring to nodes that are
+    /// not yielded by `nodes` are ignored.
+    ///
+    /// The resulting positions are centered on the origin.
+    pub fn layered<N, E>(nodes: N, edges: E, direction: SocketSide) -> Self
+    where
+        N: IntoIterator<Item=NI>,
+        E: IntoIterator<Item=(NodeSocket<NI>, NodeSocket<NI>)>,
+    {
+        let (node_ids, indices) = node_indices(nodes);
+        let n = node_ids.len();
+        if n == 0 {
+            return Layout { map: HashMap::new() };
+        }
+
+        // The outgoing neighbours of each node, skipping self-loops and duplicate edges.
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for (start, end) in edges {
+            let a = match indices.get(&start.id) { Some(&a) => a, None => continue };
+            let b = match indices.get(&end.id) { Some(&b) => b, None => continue };
+            if a != b && !outgoing[a].contains(&b) {
+                outgoing[a].push(b);
+            }
+        }
+
+        // Remove the edges that close cycles so that the remaining graph is acyclic.
+        remove_back_edges(&mut outgoing);
+
+        // Rank each node by the longest path leading to it.
+        let mut in_degree = vec![0; n];
+        for targets in &outgoing {
+            for &b in targets {
+                in_degree[b] += 1;
+            }
+        }
+        let mut ranks = vec![0; n];
+        let mut ready: Vec<usize> = (0..n).rev().filter(|&i| in_degree[i] == 0).collect();
+        while let Some(a) = ready.pop() {
+            for &b in &outgoing[a] {
+                ranks[b] = ::std::cmp::max(ranks[b], ranks[a] + 1);
+                in_degree[b] -= 1;
+                if in_degree[b] == 0 {
+                    ready.push(b);
+                }
+            }
+        }
+
+        // Spread the nodes of each layer across the layer.
+        let n_layers = ranks.iter().cloned().max().unwrap_or(0) + 1;
+        let mut layer_lens = vec![0; n_layers];
+        for &rank in &ranks {
+            layer_lens[rank] += 1;
+        }
+        let mut layer_indices = vec![0; n_layers];
+        let points = ranks
+            .iter()
+            .map(|&rank| {
+                let index = layer_indices[rank];
+                layer_indices[rank] += 1;
+                let across = (index as Scalar - (layer_lens[rank] - 1) as Scalar / 2.0)
+                    * LAYERED_NODE_SPACING;
+                let along = rank as Scalar * LAYERED_LAYER_SPACING;
+                match direction {
+                    SocketSide::Bottom => [across, -along],
+                    SocketSide::Top => [across, along],
+                    SocketSide::Right => [along, -across],
+                    SocketSide::Left => [-along, -across],
+                }
+            })
+            .collect();
+
+        centered_layout(node_ids, points)
+    }
+
+    /// Align the given nodes along the given side of the group.
+    ///
+    /// E.g. `SocketSide::Left` moves each node horizontally so that it shares the `x` position of
+    /// the leftmost node, while `SocketSide::Top` moves each node vertically so that it shares the
+    /// `y` position of the topmost node. As the layout only describes the centre of each node, nodes
+    /// are aligned by their centres.
+    ///
+    /// Nodes that are not present within the layout are ignored. Aligning fewer than two nodes
+    /// does nothing.
+    pub fn align<I>(&mut self, nodes: I, side: SocketSide)
+    where
+        I: IntoIterator<Item=NI>,
+    {
+        let node_ids = self.present(nodes);
+        if node_ids.len() < 2 {
+            return;
+        }
+        let (axis, towards_max) = match side {
+            SocketSide::Left => (0, false),
+            SocketSide::Right => (0, true),
+            SocketSide::Bottom => (1, false),
+            SocketSide::Top => (1, true),
+        };
+        let values = node_ids.iter().map(|id| self.map[id][axis]);
+        let target = match towards_max {
+            true => values.fold(::std::f64::MIN, Scalar::max),
+            false => values.fold(::std::f64::MAX, Scalar::min),
+        };
+        for id in &node_ids {
+            if let Some(point) = self.map.get_mut(id) {
+                point[axis] = target;
+            }
+        }
+    }
+
+    /// Space the given nodes evenly along the given axis.
+    ///
+    /// The two outermost nodes along the axis keep their positions while those in between are
+    /// moved along the axis so that the distance between each pair of neighbouring nodes is equal.
+    /// The order of the nodes along the axis is preserved.
+    ///
+    /// Nodes that are not present within the layout are ignored. Distributing fewer than three
+    /// nodes does nothing.
+    pub fn distribute<I>(&mut self, nodes: I, axis: Axis)
+    where
+        I: IntoIterator<Item=NI>,
+    {
+        let mut node_ids = self.present(nodes);
+        if node_ids.len() < 3 {
+            return;
+        }
+        let axis = match axis {
+            Axis::X => 0,
+            Axis::Y => 1,
+        };
+        node_ids.sort_by(|a, b| {
+            let (a, b) = (self.map[a][axis], self.map[b][axis]);
+            a.partial_cmp(&b).unwrap_or(::std::cmp::Ordering::Equal)
+        });
+        let first = self.map[&node_ids[0]][axis];
+        let last = self.map[&node_ids[node_ids.len() - 1]][axis];
+        let step = (last - first) / (node_ids.len() - 1) as Scalar;
+        for (i, id) in node_ids.iter().enumerate() {
+            if let Some(point) = self.map.get_mut(id) {
+                point[axis] = first + step * i as Scalar;
+            }
+        }
+    }
+
+    // The given nodes that are present within the layout, without duplicates.
+    fn present<I>(&self, nodes: I) -> Vec<NI>
+    where
+        I: IntoIterator<Item=NI>,
+    {
+        let mut node_ids: Vec<NI> = Vec::new();
+        for id in nodes {
+            if self.map.contains_key(&id) && !node_ids.contains(&id) {
+                node_ids.push(id);
+            }
+        }
+        node_ids
+    }
+}
+
+// Remove all edges that close a cycle within the given adjacency list.
+//
+// Nodes are visited depth-first in index order and any edge leading back to a node that is
+// currently on the stack is removed.
+fn remove_back_edges(outgoing: &mut Vec<Vec<usize>>) {
+    #[derive(Copy, Clone, PartialEq)]
+    enum Visit { Unvisited, OnStack, Done }
+    let n = outgoing.len();
+    let mut visits = vec![Visit::Unvisited; n];
+    for root in 0..n {
+        if visits[root] != Visit::Unvisited {
+            continue;
+        }
+        // A stack of nodes along with the index of the next outgoing edge to visit.
+        let mut stack = vec![(root, 0)];
+        visits[root] = Visit::OnStack;
+        while let Some(&mut (a, ref mut edge_index)) = stack.last_mut() {
+            match outgoing[a].get(*edge_index).cloned() {
+                None => {
+                    visits[a] = Visit::Done;
+                    stack.pop();
+                },
+                Some(b) => match visits[b] {
+                    Visit::OnStack => {
+                        outgoing[a].remove(*edge_index);
+                    },
+                    Visit::Done => *edge_index += 1,
+                    Visit::Unvisited => {
+                        *edge_index += 1;
+                        visits[b] = Visit::OnStack;
+                        stack.push((b, 0));
+                    },
+                },
+            }
+        }
+    }
+}
+
+// Collect the given node identifiers into a list along with a map from each ID to its index.
+//
+// Duplicate node identifiers are ignored.
+pub(crate) fn node_indices<N, NI>(nodes: N) -> (Vec<NI>, HashMap<NI, usize>)
+where
+    N: IntoIterator<Item=NI>,
+    NI: NodeId,
+{
+    let mut node_ids = Vec::new();
+    let mut indices = HashMap::new();
+    for node_id in nodes {
+        if indices.contains_key(&node_id) {
+            continue;
+        }
+        indices.insert(node_id, node_ids.len());
+        node_ids.push(node_id);
+    }
+    (node_ids, indices)
+}
+
+// Produce a `Layout` from the given nodes and points with the bounding box centered on the origin.
+pub(crate) fn centered_layout<NI>(node_ids: Vec<NI>, points: Vec<Point>) -> Layout<NI>
+where
+    NI: NodeId,
+{
+    let mut min = [::std::f64::MAX; 2];
+    let mut max = [::std::f64::MIN; 2];
+    for p in &points {
+        min = [min[0].min(p[0]), min[1].min(p[1])];
+        max = [max[0].max(p[0]), max[1].max(p[1])];
+    }
+    let centre = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+    let map = node_ids
+        .into_iter()
+        .zip(points)
+        .map(|(id, p)| (id, [p[0] - centre[0], p[1] - centre[1]]))
+        .collect();
+    Layout { map }
+}
+
+// The length of the given vector.
+fn magnitude(v: [Scalar; 2]) -> Scalar {
+    (v[0] * v[0] + v[1] * v[1]).sqrt()
+}
+
+// A tiny xorshift pseudo-random number generator.
+//
+// Used so that seeded layouts are reproducible without depending on an external crate.
+struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        XorShift { state }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    // A value within the range `0.0..1.0`.
+    fn next_scalar(&mut self) -> Scalar {
+        (self.next_u64() >> 11) as Scalar / (1u64 << 53) as Scalar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two connected components: a triangle and a chain, along with a disconnected node.
+    fn graph() -> (Vec<usize>, Vec<(NodeSocket<usize>, NodeSocket<usize>)>) {
+        let socket = |id| NodeSocket { id, socket_index: 0 };
+        let nodes = (0..7).collect();
+        let edges = vec![(0, 1), (1, 2), (2, 0), (3, 4), (4, 5)]
+            .into_iter()
+            .map(|(a, b)| (socket(a), socket(b)))
+            .collect();
+        (nodes, edges)
+    }
+
+    #[test]
+    fn force_directed_is_deterministic_for_a_seed() {
+        let (nodes, edges) = graph();
+        let a = Layout::force_directed(nodes.clone(), edges.clone(), 100, 42);
+        let b = Layout::force_directed(nodes.clone(), edges.clone(), 100, 42);
+        assert_eq!(a, b);
+        assert_eq!(a.len(), nodes.len());
+        for point in a.values() {
+            assert!(point[0].is_finite() && point[1].is_finite());
+        }
+    }
+}
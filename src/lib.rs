@@ -1,23 +1,51 @@
 #[macro_use] extern crate conrod;
 #[macro_use] extern crate conrod_derive;
+#[cfg(feature = "petgraph")] extern crate petgraph;
+#[cfg(feature = "serde")] extern crate serde;
 
-use conrod::{color, widget, Color, Colorable, Point, Positionable, Scalar, Widget, Ui, UiCell};
+use conrod::{color, widget, Color, Colorable, Point, Positionable, Scalar, Sizeable, Widget};
+use conrod::{Ui, UiCell};
+use conrod::event;
+use conrod::input::{Key, ModifierKey, MouseButton};
+use conrod::position::Rect;
 use conrod::utils::IterDiff;
+use conrod::widget::primitive::shape::triangles::{ColoredPoint, MultiColor, SingleColor, Triangle};
 use std::any::{Any, TypeId};
 use std::cell::Cell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
-pub use self::node::{Node, SocketLayout, SocketSide};
+pub use self::annotation::Annotation;
+pub use self::data::GraphData;
+pub use self::history::GraphHistory;
+pub use self::interner::{InternedId, Interner};
+pub use self::minimap::Minimap;
+pub use self::node::{Node, SocketLayout, SocketShape, SocketSide, SocketType};
+pub use self::path::Join;
+pub use self::subgraph::Subgraph;
 
+pub mod annotation;
+pub mod data;
+pub mod history;
+pub mod interner;
+pub mod layout;
+pub mod minimap;
 pub mod node;
+pub mod path;
+pub mod subgraph;
+#[cfg(feature = "petgraph")]
+pub mod petgraph_impls;
 
 /// Traits required by types that may be used as a graph node identifier.
 ///
 /// This trait has a blanket implementation for all types that satisfy the bounds.
+///
+/// Identifiers that are not `Copy` (e.g. `String`s) may be used by interning them via an
+/// `Interner` and identifying nodes by the resulting `InternedId`s.
 pub trait NodeId: 'static + Copy + Clone + PartialEq + Eq + Hash + Send {}
 impl<T> NodeId for T where T: 'static + Copy + Clone + PartialEq + Eq + Hash + Send {}
 
@@ -26,7 +54,12 @@ impl<T> NodeId for T where T: 'static + Copy + Clone + PartialEq + Eq + Hash + S
 /// All positions are relative to the centre of the `Graph` widget.
 ///
 /// Nodes can be moved by 
+///
+/// When the `serde` feature is enabled, `Layout` may be serialized and deserialized as a map from
+/// node identifiers to positions, so long as the node identifier type itself supports it.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct Layout<NI>
 where
     NI: Eq + Hash,
@@ -53,8 +86,24 @@ where
     }
 }
 
+/// The **Layout** borrowed by the **Graph** widget.
+#[derive(Debug)]
+pub enum LayoutRef<'a, NI>
+where
+    NI: 'a + Eq + Hash,
+{
+    /// The layout is only read. Drags must be applied by handling `NodeEvent::Dragged`.
+    Ref(&'a Layout<NI>),
+    /// Dragged positions are written directly to the layout during the **Graph**'s update.
+    Mut(&'a mut Layout<NI>),
+}
+
 /// A widget used for visualising and manipulating **Graph** types.
-#[derive(Clone, Debug, WidgetCommon)]
+///
+/// The **Graph** builder is not `Clone`, as it may hold the `&mut Layout` given to
+/// `Graph::new_mut` along with boxed functions such as the `ConnectionValidator`. To instantiate
+/// similar graphs, construct a new builder for each via `Graph::new`.
+#[derive(Debug, WidgetCommon)]
 pub struct Graph<'a, N, E>
 where
     N: Iterator,
@@ -71,7 +120,231 @@ where
     /// All edges within the graph.
     pub edges: E,
     /// The position of each node within the graph.
-    pub layout: &'a Layout<N::Item>,
+    pub layout: LayoutRef<'a, N::Item>,
+    /// Produces a label for a node when debug drawing is enabled.
+    ///
+    /// When `Some`, each node is labelled with its identifier and each of its sockets with their
+    /// index.
+    pub debug_label: Option<fn(&N::Item) -> String>,
+    /// Whether or not to emit `NodeEvent::Removed` for nodes that are no longer in the graph.
+    pub prune_layout: bool,
+    /// The interaction used to create edges between sockets.
+    pub connect_mode: ConnectMode,
+    /// The maximum number of nodes that may be instantiated for the first time per update.
+    pub max_nodes_per_frame: Option<usize>,
+    /// A coherent set of colors for the graph, its nodes and its edges, if any.
+    pub colors: Option<GraphColors>,
+    /// The default styling for edges produced by `Edge::straight_line`.
+    pub edge_style: EdgeStyle,
+    /// The default styling for nodes produced by `NodeContext::styled_widget`.
+    pub node_style: node::Style,
+    /// The styling for labels drawn via `Edge::label`.
+    pub edge_label_style: EdgeLabelStyle,
+    /// The size of the grid to which dragged nodes are snapped, if any.
+    pub grid_snap: Option<Scalar>,
+    /// Whether or not to skip nodes that lie entirely outside of the **Graph**'s rectangle.
+    pub cull_offscreen: bool,
+    /// The layer at which edges are instantiated, if any.
+    ///
+    /// Nodes in layers above this are yielded by `SessionEdges::foreground_nodes` rather than by
+    /// `SessionNodes::nodes`.
+    pub edge_layer: Option<i8>,
+    /// The modifier key that, while held, constrains node dragging to a single axis, if any.
+    pub axis_lock_modifier: Option<ModifierKey>,
+    /// The maximum number of edges that may be connected to each input socket and how attempts to
+    /// exceed it are handled, if any.
+    pub input_capacity: Option<(usize, InputOverflow)>,
+    /// How edges are compared when rejecting attempts to create an edge that already exists, if
+    /// duplicate edges are rejected.
+    pub duplicate_edges: Option<Directionality>,
+    /// Whether or not the graph's edges are directed from their output to their input socket.
+    pub directed: bool,
+    /// Determines whether or not the user may create an edge between two sockets, if any.
+    pub connection_validator: Option<ConnectionValidator<'a, N::Item>>,
+    /// Produces the identity by which edges are compared between updates, if any.
+    pub edge_key: Option<EdgeKey<'a, N::Item>>,
+    /// The widget drawn behind the graph in place of the colored rectangle, if any.
+    pub background: Option<Background<'a>>,
+    /// How long the mouse must rest over a socket before its tooltip is shown, if tooltips are
+    /// enabled.
+    pub socket_tooltip_delay: Option<Duration>,
+    /// The camera through which the graph is viewed, if given.
+    pub camera: Option<Camera>,
+    /// The number of pooled `widget::Id`s per widget type beyond which unused `Id`s are released,
+    /// if any.
+    pub widget_id_pool_limit: Option<usize>,
+    /// The distance by which each arrow key press pans the camera.
+    pub pan_step: Scalar,
+    /// The distance from the mouse within which an edge being created snaps to the nearest socket
+    /// upon completion.
+    pub socket_snap_radius: Scalar,
+    /// The smallest zoom permitted for the camera.
+    pub min_zoom: Scalar,
+    /// The largest zoom permitted for the camera.
+    pub max_zoom: Scalar,
+    /// The identity and revision of the `GraphData` yielding the nodes and edges, if the graph was
+    /// built via `Graph::from_data`.
+    pub data_key: Option<(usize, u64)>,
+}
+
+/// A function that determines whether or not the user may create an edge from the given `start`
+/// (output) socket to the given `end` (input) socket.
+///
+/// See `Graph::validate_connection`.
+pub struct ConnectionValidator<'a, NI> {
+    validate: Box<Fn(NodeSocket<NI>, NodeSocket<NI>) -> bool + 'a>,
+}
+
+impl<'a, NI> ConnectionValidator<'a, NI> {
+    /// Whether or not an edge from `start` to `end` is permitted.
+    pub fn is_valid(&self, start: NodeSocket<NI>, end: NodeSocket<NI>) -> bool {
+        (self.validate)(start, end)
+    }
+}
+
+impl<'a, NI> std::fmt::Debug for ConnectionValidator<'a, NI> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "ConnectionValidator")
+    }
+}
+
+/// A function producing the identity of the edge from the given `start` (output) socket to the
+/// given `end` (input) socket, by which edges are compared between updates.
+///
+/// See `Graph::edge_key`.
+pub struct EdgeKey<'a, NI> {
+    key: Box<Fn(NodeSocket<NI>, NodeSocket<NI>) -> u64 + 'a>,
+}
+
+impl<'a, NI> EdgeKey<'a, NI> {
+    /// The identity of the edge from `start` to `end`.
+    pub fn key(&self, start: NodeSocket<NI>, end: NodeSocket<NI>) -> u64 {
+        (self.key)(start, end)
+    }
+}
+
+impl<'a, NI> std::fmt::Debug for EdgeKey<'a, NI> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "EdgeKey")
+    }
+}
+
+/// A custom widget drawn behind the graph's nodes and edges.
+///
+/// See `Graph::background`.
+pub struct Background<'a> {
+    // Instantiates the widget with the given `widget::Id` over the graph's `Rect`.
+    set: Box<FnMut(widget::Id, widget::Id, Rect, &mut UiCell) + 'a>,
+}
+
+impl<'a> std::fmt::Debug for Background<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Background")
+    }
+}
+
+/// The default thickness of edges produced by `Edge::straight_line`.
+pub const DEFAULT_EDGE_THICKNESS: Scalar = 3.0;
+
+/// Styling for the edges produced by `Edge::straight_line`.
+///
+/// This is set graph-wide via `Graph::edge_style` and may be overridden per edge via the
+/// `EdgeWidget` builder methods.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EdgeStyle {
+    /// The thickness of the edge.
+    pub thickness: Scalar,
+    /// The color of the edge.
+    ///
+    /// If `None`, the `edge` color of the graph's `GraphColors` is used if there are any.
+    /// Otherwise the edge is `DARK_CHARCOAL`.
+    pub color: Option<Color>,
+    /// The style of the edge's end caps, if any.
+    pub cap: Option<widget::line::Cap>,
+}
+
+/// Styling for the labels drawn at the midpoint of edges via `Edge::label`.
+///
+/// This is set graph-wide via `Graph::edge_label_style`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EdgeLabelStyle {
+    /// The color of the label's text.
+    pub color: Color,
+    /// The font size of the label's text.
+    pub font_size: conrod::FontSize,
+    /// The color of the rounded "pill" drawn behind the text for legibility, if any.
+    pub background_color: Option<Color>,
+    /// The padding between the text and the edge of its background.
+    pub padding: Scalar,
+}
+
+/// A coherent set of colors for the **Graph**, its nodes and its edges.
+///
+/// The colors are made available to the node and edge instantiation stages via
+/// `Session::colors`. They are applied to `node::Node` widgets via `Node::graph_colors` and used
+/// by default for the widgets produced by the `Edge` methods.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GraphColors {
+    /// The color of the **Graph**'s background rectangle.
+    pub background: Color,
+    /// The color of each node's inner rectangle.
+    pub node: Color,
+    /// The color of each node's border.
+    pub node_border: Color,
+    /// The color of each node's sockets.
+    pub socket: Color,
+    /// The color of each edge.
+    pub edge: Color,
+}
+
+/// Describes the interaction used to create an edge between two sockets.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConnectMode {
+    /// Press the left mouse button over a socket, drag to another socket and release.
+    PressDrag,
+    /// Click a socket to begin the edge and click another socket to complete it.
+    ///
+    /// Clicking anywhere other than a valid socket cancels the edge.
+    ClickClick,
+}
+
+/// Describes how an attempt to connect an edge to a full input socket is handled.
+///
+/// See `Graph::input_capacity`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum InputOverflow {
+    /// Emit an `EdgeEvent::Cancelled` in place of the `EdgeEvent::Add`.
+    Reject,
+    /// Emit an `EdgeEvent::Remove` for the earliest edge(s) connected to the input socket
+    /// followed by the `EdgeEvent::Add`, replacing the existing connection.
+    Replace,
+}
+
+/// Describes whether or not the direction of an edge distinguishes it from other edges.
+///
+/// See `Graph::reject_duplicate_edges` and `Session::contains_edge`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Directionality {
+    /// The edges `(a, b)` and `(b, a)` are distinct.
+    Directed,
+    /// The edges `(a, b)` and `(b, a)` are the same edge.
+    Undirected,
+}
+
+impl Directionality {
+    /// Whether or not the two given edges are the same edge.
+    ///
+    /// Each edge is described by its start and end sockets.
+    pub fn is_same_edge<NI>(&self, a: (NodeSocket<NI>, NodeSocket<NI>),
+                            b: (NodeSocket<NI>, NodeSocket<NI>)) -> bool
+    where
+        NI: NodeId,
+    {
+        match *self {
+            Directionality::Directed => a == b,
+            Directionality::Undirected => a == b || (a.0, a.1) == (b.1, b.0),
+        }
+    }
 }
 
 /// Unique styling for the **BorderedRectangle** widget.
@@ -80,15 +353,58 @@ pub struct Style {
     /// Shape styling for the inner rectangle.
     #[conrod(default = "color::TRANSPARENT")]
     pub background_color: Option<Color>,
+    /// The spacing between the lines of the background grid, if any.
+    #[conrod(default = "None")]
+    pub grid_spacing: Option<Option<Scalar>>,
+    /// The color of the background grid lines.
+    #[conrod(default = "color::rgba(1.0, 1.0, 1.0, 0.05)")]
+    pub grid_color: Option<Color>,
+    /// Default layout for the input sockets of nodes produced by `NodeContext::styled_widget`.
+    #[conrod(default = "SocketLayout::inputs_on_left()")]
+    pub input_socket_layout: Option<SocketLayout>,
+    /// Default layout for the output sockets of nodes produced by `NodeContext::styled_widget`.
+    #[conrod(default = "SocketLayout::outputs_on_right()")]
+    pub output_socket_layout: Option<SocketLayout>,
+    /// The distance the mouse must move while the left button is held before a node is dragged
+    /// or a box selection begins.
+    #[conrod(default = "3.0")]
+    pub drag_threshold: Option<Scalar>,
 }
 
+// The thickness of the background grid lines.
+const GRID_LINE_THICKNESS: Scalar = 1.0;
+
 widget_ids! {
     struct Ids {
         // The rectangle over which all nodes are placed.
         background,
+        // The custom widget given via `Graph::background`, drawn in place of the rectangle.
+        custom_background,
+        // The background grid lines.
+        grid,
+        // Text labels for node identifiers and socket indices when debug drawing is enabled.
+        debug_labels[],
+        // The tooltip describing the hovered socket and the rectangle behind it.
+        socket_tooltip,
+        socket_tooltip_background,
+        // The cross drawn at the mouse while the edge being created is over an invalid target.
+        invalid_target_indicator,
     }
 }
 
+// The depth of the background of edge labels, placing them above the nodes and edges.
+const EDGE_LABEL_DEPTH: Scalar = -1.0;
+// The font size used for debug labels.
+const DEBUG_LABEL_FONT_SIZE: conrod::FontSize = 10;
+// The font size used for socket tooltips.
+const SOCKET_TOOLTIP_FONT_SIZE: conrod::FontSize = 12;
+// The padding between a socket tooltip's text and the edge of its background.
+const SOCKET_TOOLTIP_PAD: Scalar = 4.0;
+// The width and height of the cross drawn at the mouse over an invalid target socket.
+const INVALID_TARGET_INDICATOR_SIZE: Scalar = 12.0;
+// The thickness of the strokes of the cross drawn over an invalid target socket.
+const INVALID_TARGET_INDICATOR_THICKNESS: Scalar = 3.0;
+
 /// Unique state for the `Graph`.
 pub struct State<NI>
 where
@@ -112,8 +428,351 @@ where
     node_ids: Vec<NI>,
     // A list of all edges where (a, b) represents the directed edge a -> b.
     edges: Vec<(NodeSocket<NI>, NodeSocket<NI>)>,
+    // For each edge, its index among the edges that share its start and end nodes along with
+    // the total number of edges sharing those nodes.
+    parallel_edges: Vec<(usize, usize)>,
     // A map from type identifiers to available `widget::Id`s for those types.
     widget_id_map: WidgetIdMap<NI>,
+    // The in-progress box selection in the space of the `Layout`, if any.
+    box_selection: Option<Rect>,
+    // The widget over which the current left drag began (a node or the graph itself) and whether
+    // or not the drag has exceeded the drag threshold. `None` while the left button is up.
+    drag_engaged: Option<(widget::Id, bool)>,
+    // The node whose drag gesture has started but not yet ended, if any.
+    dragging_node: Option<NI>,
+    // Whether or not a node widget is currently capturing the mouse, e.g. while being dragged or
+    // while an edge is being created from one of its sockets.
+    pressing_node: bool,
+    // Whether or not the camera was panned via the arrow keys during this update.
+    panned: bool,
+    // The socket from which an edge is currently being created, if any.
+    connecting: Option<(SocketType, NodeSocket<NI>)>,
+    // Whether or not the edge being created was begun by pressing the left mouse button, in
+    // which case the button is expected to be held until the edge is completed.
+    connecting_by_press: bool,
+    // The node whose widget (or one of its descendants) is under the mouse, recorded during node
+    // instantiation.
+    hovered_node: Option<NI>,
+    // The node whose widget rectangle contained the mouse during the last update, if any.
+    //
+    // Used to emit `Hovered` and `Unhovered` events only when the mouse enters or leaves a node.
+    mouse_over_node: Option<NI>,
+    // The number of updates the graph has begun, including the current update.
+    update_count: u64,
+    // The update during which each node first appeared, for nodes that were not present during
+    // the graph's first update.
+    node_appearances: HashMap<NI, u64>,
+    // The identity and revision of the `GraphData` from which the nodes and edges were last
+    // updated, if any.
+    data_key: Option<(usize, u64)>,
+    // The socket under the mouse as of the last update, used to emit `SocketHovered` and
+    // `SocketUnhovered` events.
+    socket_under_mouse: Option<(SocketType, NodeSocket<NI>)>,
+    // The socket under the mouse while no button is pressed, along with the moment at which the
+    // mouse began hovering over it.
+    hovered_socket: Option<(SocketType, NodeSocket<NI>, Instant)>,
+    // The node currently being dragged along with the edge beneath it, if any.
+    edge_under_drag: Option<(NI, (NodeSocket<NI>, NodeSocket<NI>))>,
+    // The position of each node as of the last update, along with the position given by the
+    // `Layout` at the time.
+    //
+    // The former is authoritative for as long as the `Layout` position remains unchanged, so that
+    // dragged nodes keep their position even when the `Layout` is borrowed immutably and is not
+    // updated in response to `Dragged` events.
+    node_points: HashMap<NI, (Point, Point)>,
+    // The unsnapped position of each node being dragged while grid snapping is enabled.
+    //
+    // This allows drags smaller than the grid size to accumulate across updates.
+    unsnapped_drags: HashMap<NI, Point>,
+    // Nodes that have been yielded for instantiation at least once.
+    instantiated: HashSet<NI>,
+    // Nodes whose first instantiation has been deferred to a later update.
+    deferred: HashSet<NI>,
+    // The dimensions of each node's widget from the last update in which it was instantiated.
+    node_dims: HashMap<NI, conrod::Dimensions>,
+    // Nodes that lie entirely outside of the graph's rectangle and are skipped this update.
+    culled: HashSet<NI>,
+    // The edge whose end has been grabbed from its input socket for reconnecting, if any.
+    //
+    // While reconnecting, `connecting` holds the start of the edge.
+    reconnecting: Option<(NodeSocket<NI>, NodeSocket<NI>)>,
+    // The sockets near the mouse to which the edge being created may be connected.
+    drop_targets: HashSet<(SocketType, NodeSocket<NI>)>,
+    // The socket under the mouse to which the edge being created may not be connected, if any.
+    invalid_target: Option<(SocketType, NodeSocket<NI>)>,
+    // The connection rejected by the `ConnectionValidator` during this update, if any.
+    rejected_connection: Option<(NodeSocket<NI>, NodeSocket<NI>)>,
+    // The key produced by the `Graph::edge_key` function for each of the `edges`. Empty if no
+    // function was given.
+    edge_keys: Vec<u64>,
+    // The layer assigned to each node during its last instantiation.
+    node_layers: HashMap<NI, i8>,
+    // The layer at which edges are instantiated, if any.
+    edge_layer: Option<i8>,
+    // All pairs of overlapping sockets found during the last update.
+    socket_overlaps: Vec<(SocketOverlap<NI>, SocketOverlap<NI>)>,
+    // A spatial index of socket rectangles for mapping positions to sockets.
+    socket_grid: SocketGrid<NI>,
+    // The set of colors given to the graph, if any.
+    colors: Option<GraphColors>,
+    // The default styling for edges.
+    edge_style: EdgeStyle,
+    // The default styling for nodes.
+    node_style: node::Style,
+    // The styling for edge labels.
+    edge_label_style: EdgeLabelStyle,
+    // Whether or not the graph's edges are directed, as set via `Graph::directed`.
+    directed: bool,
+    // The default socket layouts for nodes whose style does not specify their own.
+    socket_layouts: SocketLayouts,
+    // The camera through which the graph is viewed.
+    camera: Camera,
+}
+
+/// A socket described by its node, index and type, as reported by `Session::socket_overlaps`.
+pub type SocketOverlap<NI> = (SocketType, NodeSocket<NI>);
+
+// The width and height of each cell within the `SocketGrid`.
+const SOCKET_GRID_CELL_SIZE: Scalar = 64.0;
+
+/// The distance from the mouse within which sockets are considered as drop targets while an edge
+/// is being created.
+pub const DROP_TARGET_RADIUS: Scalar = 100.0;
+
+/// The default distance from the mouse within which an edge being created snaps to the nearest
+/// socket upon completion.
+pub const DEFAULT_SOCKET_SNAP_RADIUS: Scalar = 16.0;
+
+// A uniform grid of socket rectangles in absolute coordinates, rebuilt once per update from the
+// rectangles of the node widgets.
+//
+// Each socket is stored within every cell that its rectangle overlaps, so that finding the socket
+// at some position only requires checking the sockets within a single cell.
+struct SocketGrid<NI> {
+    cells: HashMap<(i64, i64), Vec<(SocketType, NodeSocket<NI>, Rect)>>,
+}
+
+impl<NI> SocketGrid<NI>
+where
+    NI: NodeId,
+{
+    // The index of the cell containing the given absolute position.
+    fn cell(xy: Point) -> (i64, i64) {
+        let x = (xy[0] / SOCKET_GRID_CELL_SIZE).floor() as i64;
+        let y = (xy[1] / SOCKET_GRID_CELL_SIZE).floor() as i64;
+        (x, y)
+    }
+
+    // Rebuild the grid from the sockets of the given node widgets.
+    fn rebuild(&mut self, node_widget_ids: &HashMap<NI, widget::Id>, ui: &Ui) {
+        for sockets in self.cells.values_mut() {
+            sockets.clear();
+        }
+        for (&id, &widget_id) in node_widget_ids {
+            let (inputs, outputs) = match node::socket_rects(widget_id, ui) {
+                Some(rects) => rects,
+                None => continue,
+            };
+            let sockets = inputs.enumerate()
+                .map(|(i, rect)| (SocketType::Input, i, rect))
+                .chain(outputs.enumerate().map(|(i, rect)| (SocketType::Output, i, rect)));
+            for (ty, socket_index, rect) in sockets {
+                self.insert(ty, NodeSocket { id, socket_index }, rect);
+            }
+        }
+        self.cells.retain(|_, sockets| !sockets.is_empty());
+    }
+
+    // Add the given socket to every cell overlapped by its rectangle.
+    fn insert(&mut self, ty: SocketType, socket: NodeSocket<NI>, rect: Rect) {
+        let (min_x, min_y) = Self::cell(rect.bottom_left());
+        let (max_x, max_y) = Self::cell(rect.top_right());
+        for x in min_x..max_x + 1 {
+            for y in min_y..max_y + 1 {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push((ty, socket, rect));
+            }
+        }
+    }
+
+    // All sockets whose rectangles lie within the cells overlapping the square of the given
+    // half-size centred on the given absolute position.
+    //
+    // A socket spanning multiple cells may be yielded more than once.
+    fn sockets_near<'a>(&'a self, xy: Point, radius: Scalar)
+        -> Box<Iterator<Item=(SocketType, NodeSocket<NI>, Rect)> + 'a>
+    {
+        let (min_x, min_y) = Self::cell([xy[0] - radius, xy[1] - radius]);
+        let (max_x, max_y) = Self::cell([xy[0] + radius, xy[1] + radius]);
+        let cells = (min_x..max_x + 1).flat_map(move |x| (min_y..max_y + 1).map(move |y| (x, y)));
+        let sockets = cells
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flat_map(|sockets| sockets.iter().cloned());
+        Box::new(sockets)
+    }
+
+    // The socket whose rectangle contains the given absolute position.
+    //
+    // If the rectangles of multiple sockets contain the position, the socket whose centre is
+    // nearest is returned.
+    fn socket_at(&self, xy: Point) -> Option<(SocketType, NodeSocket<NI>)> {
+        let distance = |rect: &Rect| {
+            let [x, y] = rect.xy();
+            (x - xy[0]).powi(2) + (y - xy[1]).powi(2)
+        };
+        self.cells
+            .get(&Self::cell(xy))
+            .into_iter()
+            .flat_map(|sockets| sockets.iter())
+            .filter(|&&(_, _, ref rect)| rect.is_over(xy))
+            .min_by(|a, b| distance(&a.2).partial_cmp(&distance(&b.2)).unwrap())
+            .map(|&(ty, socket, _)| (ty, socket))
+    }
+
+    // The socket whose rectangle is nearest to the given absolute position, ignoring sockets
+    // further than `radius` away and, if a `socket_type` is given, sockets of the other type.
+    //
+    // A socket whose rectangle contains the position has a distance of `0.0`.
+    fn nearest_socket(&self, xy: Point, radius: Scalar, socket_type: Option<SocketType>)
+        -> Option<(SocketType, NodeSocket<NI>)>
+    {
+        let distance = |rect: &Rect| {
+            let (l, r, b, t) = rect.l_r_b_t();
+            let dx = (l - xy[0]).max(xy[0] - r).max(0.0);
+            let dy = (b - xy[1]).max(xy[1] - t).max(0.0);
+            (dx * dx + dy * dy).sqrt()
+        };
+        self.sockets_near(xy, radius)
+            .filter(|&(ty, _, _)| socket_type.map(|t| t == ty).unwrap_or(true))
+            .map(|(ty, socket, rect)| (distance(&rect), ty, socket))
+            .filter(|&(d, _, _)| d <= radius)
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .map(|(_, ty, socket)| (ty, socket))
+    }
+}
+
+impl<NI> Shared<NI>
+where
+    NI: NodeId,
+{
+    // The socket at the given absolute position, according to the socket grid built during the
+    // current update.
+    fn socket_at(&self, xy: Point) -> Option<(SocketType, NodeSocket<NI>)> {
+        self.socket_grid.socket_at(xy)
+    }
+
+    // The state shared with the **Session** before the graph's first update.
+    fn new(directed: bool) -> Self {
+        let events = VecDeque::new();
+        let nodes = HashMap::new();
+        let node_ids = Vec::new();
+        let edges = Vec::new();
+        let parallel_edges = Vec::new();
+        let type_widget_ids = HashMap::new();
+        let node_widget_ids = HashMap::new();
+        let widget_id_map = WidgetIdMap { type_widget_ids, node_widget_ids };
+        let box_selection = None;
+        let drag_engaged = None;
+        let dragging_node = None;
+        let pressing_node = false;
+        let panned = false;
+        let connecting = None;
+        let connecting_by_press = false;
+        let hovered_node = None;
+        let mouse_over_node = None;
+        let socket_under_mouse = None;
+        let data_key = None;
+        let update_count = 0;
+        let node_appearances = HashMap::new();
+        let hovered_socket = None;
+        let edge_under_drag = None;
+        let node_points = HashMap::new();
+        let unsnapped_drags = HashMap::new();
+        let instantiated = HashSet::new();
+        let deferred = HashSet::new();
+        let node_dims = HashMap::new();
+        let culled = HashSet::new();
+        let reconnecting = None;
+        let drop_targets = HashSet::new();
+        let invalid_target = None;
+        let rejected_connection = None;
+        let edge_keys = Vec::new();
+        let node_layers = HashMap::new();
+        let edge_layer = None;
+        let socket_overlaps = Vec::new();
+        let socket_grid = SocketGrid { cells: HashMap::new() };
+        let colors = None;
+        let edge_style = EdgeStyle::default();
+        let node_style = node::Style::default();
+        let edge_label_style = EdgeLabelStyle::default();
+        let socket_layouts = SocketLayouts {
+            input: SocketLayout::inputs_on_left(),
+            output: SocketLayout::outputs_on_right(),
+        };
+        Shared {
+            events,
+            nodes,
+            node_ids,
+            edges,
+            parallel_edges,
+            widget_id_map,
+            box_selection,
+            drag_engaged,
+            dragging_node,
+            pressing_node,
+            panned,
+            connecting,
+            connecting_by_press,
+            hovered_node,
+            mouse_over_node,
+            socket_under_mouse,
+            data_key,
+            update_count,
+            node_appearances,
+            hovered_socket,
+            edge_under_drag,
+            node_points,
+            unsnapped_drags,
+            instantiated,
+            deferred,
+            node_dims,
+            culled,
+            reconnecting,
+            drop_targets,
+            invalid_target,
+            rejected_connection,
+            edge_keys,
+            node_layers,
+            edge_layer,
+            socket_overlaps,
+            socket_grid,
+            colors,
+            edge_style,
+            node_style,
+            edge_label_style,
+            directed,
+            socket_layouts,
+            camera: Camera::new([0.0; 2], 1.0),
+        }
+    }
+
+    // Clear all state that only describes a single update, so that it cannot carry over into the
+    // next. Called once at the beginning of each update before any interaction is handled.
+    //
+    // State describing interactions that span multiple updates (e.g. the edge being created, the
+    // box selection, the edge under a dragged node) is preserved, as are any pending `events`,
+    // which may be pushed during the session stages (e.g. via `Session::begin_edge`) and are
+    // removed as they are yielded.
+    fn reset_per_update(&mut self) {
+        self.widget_id_map.reset_indices();
+        self.hovered_node = None;
+        self.pressing_node = false;
+        self.panned = false;
+        self.deferred.clear();
+        self.culled.clear();
+        self.socket_overlaps.clear();
+        self.rejected_connection = None;
+        self.drop_targets.clear();
+    }
 }
 
 // A type for managing the input and output socket layouts.
@@ -180,6 +839,27 @@ where
         }
     }
 
+    // Release the `widget::Id`s beyond the given limit from each type's list, keeping those used
+    // during the last update.
+    //
+    // As `Id`s are reused from the start of each list every update, each list only grows to the
+    // greatest number of widgets of its type instantiated within a single update, regardless of
+    // how many nodes have been added and removed over time. This allows that peak to be released
+    // once it has passed. Must be called before `reset_indices`. Types with no `Id`s remaining are
+    // forgotten entirely.
+    fn shrink_to(&mut self, limit: usize) {
+        for type_widget_ids in self.type_widget_ids.values_mut() {
+            let len = ::std::cmp::max(limit, type_widget_ids.next_index);
+            type_widget_ids.widget_ids.truncate(len);
+        }
+        self.type_widget_ids.retain(|_, type_widget_ids| !type_widget_ids.widget_ids.is_empty());
+    }
+
+    // The total number of `widget::Id`s across the lists of all types.
+    fn len(&self) -> usize {
+        self.type_widget_ids.values().map(|type_widget_ids| type_widget_ids.widget_ids.len()).sum()
+    }
+
     // Clears the `node_id` -> `widget_id` mappings so that they may be recreated during the next
     // node instantiation stage.
     fn clear_node_mappings(&mut self) {
@@ -201,6 +881,30 @@ where
         widget_id
     }
 
+    // Map the node to the given pinned `widget::Id`.
+    //
+    // Falls back to `next_id_for_node` if the `Id` belongs to one of the type pools or is already
+    // mapped to another node, as sharing the `Id` would cause the widgets to collide.
+    fn pin_id_for_node<T>(
+        &mut self,
+        node_id: NI,
+        widget_id: widget::Id,
+        generator: &mut widget::id::Generator,
+    ) -> widget::Id
+    where
+        T: Any,
+    {
+        let is_pooled = self.type_widget_ids.values()
+            .any(|type_widget_ids| type_widget_ids.widget_ids.contains(&widget_id));
+        let is_mapped = self.node_widget_ids.iter()
+            .any(|(&other, &id)| other != node_id && id == widget_id);
+        if is_pooled || is_mapped {
+            return self.next_id_for_node::<T>(node_id, generator);
+        }
+        self.node_widget_ids.insert(node_id, widget_id);
+        widget_id
+    }
+
     // Return the next `widget::Id` for a widget of the given type.
     //
     // If there are no more `Id`s available for the type, a new one will be generated from the
@@ -214,20 +918,45 @@ where
         let widget_id = type_widget_ids.next_id(generator);
         widget_id
     }
+
+    // Return the next `widget::Id` for an annotation widget of the given type.
+    //
+    // If there are no more `Id`s available for the type, a new one will be generated from the
+    // given `widget::id::Generator`.
+    fn next_id_for_annotation<T>(&mut self, generator: &mut widget::id::Generator) -> widget::Id
+    where
+        T: Any,
+    {
+        let type_id = TypeId::of::<T>();
+        let type_widget_ids = self.type_widget_ids.entry(type_id).or_insert_with(TypeWidgetIds::default);
+        type_widget_ids.next_id(generator)
+    }
 }
 
 /// An interaction has caused some event to occur.
-//
-// TODO:
-//
-// - Hovered near outlet.
-// - Edge end hovered near an outlet?
 #[derive(Clone, Debug, PartialEq)]
 pub enum Event<NI> {
     /// Events associated with nodes.
     Node(NodeEvent<NI>),
     /// Events associated with edges.
     Edge(EdgeEvent<NI>),
+    /// The user has clicked the right mouse button over the graph, requesting a context menu.
+    ///
+    /// `at` is the absolute position of the click, at which the user may instantiate their own
+    /// menu widget. `target` is the node whose widget (or one of its descendants) was clicked, or
+    /// `None` if the graph's background was clicked. Right clicks are ignored while an edge is
+    /// being created or the left mouse button is held.
+    ContextMenu {
+        at: Point,
+        target: Option<NI>,
+    },
+    /// The user has clicked the left mouse button over the graph's background without clicking a
+    /// node or dragging beyond `Graph::drag_threshold`.
+    ///
+    /// The point is in graph coordinates, i.e. the same coordinates as the `Layout`, so it may be
+    /// used to place a new node at the click location. This may also be used to clear the user's
+    /// selection of nodes.
+    BackgroundClicked(Point),
 }
 
 /// Represents a socket connection on a node.
@@ -250,12 +979,67 @@ pub struct NodeSocket<NI> {
 pub enum NodeEvent<NI> {
     /// The user attempted to remove the node with the given identifier.
     Remove(NI),
+    /// The node with the given identifier is no longer yielded by the graph's nodes.
+    ///
+    /// Only emitted when `Graph::prune_layout` is enabled. As the `Graph` never removes entries
+    /// from the `Layout`, the user is responsible for removing the node's position from their
+    /// `Layout`, e.g. via `layout.remove(node_id)`.
+    Removed(NI),
+    /// The widget used to represent this `Node` has begun to be dragged from the given position.
+    ///
+    /// Emitted immediately before the first `Dragged` event of a drag gesture.
+    DragStarted {
+        node_id: NI,
+        from: Point,
+    },
     /// The widget used to represent this `Node` has been dragged.
     Dragged {
         node_id: NI,
         from: Point,
         to: Point,
     },
+    /// The left mouse button was released, ending the drag gesture begun by the last
+    /// `DragStarted` for this node.
+    ///
+    /// `to` is the position of the node at the end of the drag. Together with the `from` of the
+    /// `DragStarted` event, this allows the whole drag to be recorded as a single move, e.g. for
+    /// an undo history.
+    DragEnded {
+        node_id: NI,
+        to: Point,
+    },
+    /// The resize handle of the node's `node::Node` widget has been dragged, requesting that the
+    /// node be resized from `from` to `to`.
+    ///
+    /// Resize handles are enabled via `Node::resizable` and `to` is constrained to the node's
+    /// `min_size` and `max_size`. As the size of each node is determined by its widget, the
+    /// **Graph** does not store node sizes. Much like the `Layout` stores the position of each
+    /// node, the user is expected to store the size of each node (e.g. within a
+    /// `HashMap<NI, Dimensions>`) and apply it to the node's widget via `wh` upon instantiation.
+    /// As nodes are positioned by their centre, the user may also wish to move the node by half of
+    /// the change in size in order to keep its top left corner in place.
+    Resized {
+        node_id: NI,
+        from: conrod::Dimensions,
+        to: conrod::Dimensions,
+    },
+    /// The widget representing the node has been double-clicked with the left mouse button.
+    ///
+    /// The individual clicks making up the double-click do not emit any events of their own.
+    DoubleClicked(NI),
+    /// The collapse chevron of the node's `node::Node` widget has been clicked, requesting that
+    /// the node be collapsed if `collapsed` is `true` or expanded otherwise.
+    ///
+    /// Like positions within the `Layout`, the collapsed state of each node is stored by the user
+    /// and applied via `Node::collapsed` upon instantiation.
+    CollapseToggled {
+        node_id: NI,
+        collapsed: bool,
+    },
+    /// The mouse has entered the rectangle of the widget representing the node.
+    Hovered(NI),
+    /// The mouse has left the rectangle of the widget representing the node.
+    Unhovered(NI),
 }
 
 /// Events related to adding and removing edges.
@@ -270,17 +1054,76 @@ pub enum EdgeEvent<NI> {
         end: NodeSocket<NI>,
     },
     /// The user has cancelled creating an edge from the given socket.
+    ///
+    /// Also emitted when the connection is rejected by the graph's `ConnectionValidator`.
     Cancelled(NodeSocket<NI>),
     /// The user has attempted to remove the edge connecting the two given sockets.
     Remove {
         start: NodeSocket<NI>,
         end: NodeSocket<NI>,
     },
+    /// The user has dragged the end of the edge connecting `old_start` to `old_end` from its input
+    /// socket and dropped it onto the input socket `new_end`.
+    ///
+    /// The end of an existing edge is grabbed by pressing an input socket to which an edge is
+    /// already connected. Dropping the end anywhere other than an input socket emits a `Remove`
+    /// for the edge instead.
+    Reconnect {
+        old_start: NodeSocket<NI>,
+        old_end: NodeSocket<NI>,
+        new_end: NodeSocket<NI>,
+    },
+    /// The mouse has entered the given socket.
+    ///
+    /// Sockets are found using the node widgets from the previous update. Unlike
+    /// `Session::hovered_socket`, sockets are entered and left regardless of whether or not a
+    /// mouse button is pressed.
+    SocketHovered {
+        socket_type: SocketType,
+        socket: NodeSocket<NI>,
+    },
+    /// The mouse has left the given socket.
+    SocketUnhovered {
+        socket_type: SocketType,
+        socket: NodeSocket<NI>,
+    },
+    /// While an edge is being created, the mouse has entered a socket that would accept it.
+    ///
+    /// Emitted immediately after the `SocketHovered` event for the socket. `start` and `end` are
+    /// the output and input sockets of the potential edge. Sockets are considered as in
+    /// `Session::is_drop_target`.
+    TargetHovered {
+        start: NodeSocket<NI>,
+        end: NodeSocket<NI>,
+    },
+    /// The user has dropped the given node onto the edge connecting the two given sockets,
+    /// requesting that the edge be split through the node.
+    SplitRequested {
+        node_id: NI,
+        start: NodeSocket<NI>,
+        end: NodeSocket<NI>,
+    },
 }
 
+/// The maximum distance between the centre of a dragged node and an edge for the node to be
+/// considered over the edge.
+pub const EDGE_DROP_TOLERANCE: Scalar = 20.0;
+
+/// The default distance by which each arrow key press pans the **Graph**'s camera.
+pub const DEFAULT_PAN_STEP: Scalar = 20.0;
+/// The factor by which the pan step is multiplied while `SHIFT` is held.
+pub const FAST_PAN_MULTIPLIER: Scalar = 5.0;
+/// The default smallest zoom permitted for the **Graph**'s camera.
+pub const DEFAULT_MIN_ZOOM: Scalar = 0.1;
+/// The default largest zoom permitted for the **Graph**'s camera.
+pub const DEFAULT_MAX_ZOOM: Scalar = 4.0;
+
 /// The camera used to view the graph.
 ///
 /// The camera supports 2D positioning and zoom.
+///
+/// The **Graph** centres its view on the camera's position, offsetting its nodes, edges and
+/// annotations accordingly. The zoom is not applied by the **Graph** itself.
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Camera {
     // The position of the camera over the floorplan.
@@ -297,16 +1140,149 @@ pub struct Camera {
     zoom: Scalar,
 }
 
+/// An iterator yielding a `Camera` for each frame of a smooth transition between two cameras.
+///
+/// Produced via `Camera::transition_to`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraTransition {
+    from: Camera,
+    to: Camera,
+    // The total number of frames over which the transition occurs.
+    frames: usize,
+    // The number of frames yielded so far.
+    frame: usize,
+}
+
+impl Camera {
+    /// A camera at the given position with the given zoom.
+    pub fn new(point: Point, zoom: Scalar) -> Self {
+        Camera { point, zoom }
+    }
+
+    /// The position of the camera relative to the centre of the graph.
+    pub fn point(&self) -> Point {
+        self.point
+    }
+
+    /// The zoom of the camera, where `1.0` is the original resolution.
+    pub fn zoom(&self) -> Scalar {
+        self.zoom
+    }
+
+    /// A camera centred on the given node without changing the zoom.
+    ///
+    /// Returns an unchanged copy of this camera if the node is not present within the `layout`.
+    pub fn focus_on<NI>(&self, node_id: NI, layout: &Layout<NI>) -> Self
+    where
+        NI: NodeId,
+    {
+        self.focus_on_with_zoom(node_id, layout, self.zoom)
+    }
+
+    /// A camera centred on the given node with the given zoom.
+    ///
+    /// Returns an unchanged copy of this camera if the node is not present within the `layout`.
+    pub fn focus_on_with_zoom<NI>(&self, node_id: NI, layout: &Layout<NI>, zoom: Scalar) -> Self
+    where
+        NI: NodeId,
+    {
+        match layout.get(node_id) {
+            Some(&point) => Camera { point, zoom },
+            None => self.clone(),
+        }
+    }
+
+    /// Linearly interpolate between this camera and the `other` by the given amount.
+    ///
+    /// `t` is expected to be within the range `0.0..=1.0`, where `0.0` produces this camera and
+    /// `1.0` produces the `other`.
+    pub fn lerp(&self, other: &Camera, t: Scalar) -> Self {
+        let lerp = |a: Scalar, b: Scalar| a + (b - a) * t;
+        let point = [lerp(self.point[0], other.point[0]), lerp(self.point[1], other.point[1])];
+        let zoom = lerp(self.zoom, other.zoom);
+        Camera { point, zoom }
+    }
+
+    /// A copy of this camera with its zoom clamped to the range between `min_zoom` and `max_zoom`.
+    ///
+    /// The **Graph** applies the same clamping to its camera each update (see `Graph::min_zoom`),
+    /// so cameras produced by `new`, `focus_on_with_zoom` or `lerp` may be clamped ahead of time
+    /// to know the zoom at which they will be viewed. The bounds are swapped if `min_zoom` is
+    /// greater than `max_zoom`.
+    pub fn clamp_zoom(&self, min_zoom: Scalar, max_zoom: Scalar) -> Self {
+        Camera { point: self.point, zoom: clamp_zoom(self.zoom, min_zoom, max_zoom) }
+    }
+
+    /// Set the zoom of the camera, clamped to the range between `min_zoom` and `max_zoom`.
+    ///
+    /// E.g. to zoom in response to the mouse wheel, pass the same bounds given to the **Graph** via
+    /// `Graph::min_zoom` and `Graph::max_zoom`, then pass the camera back via `Graph::camera`.
+    pub fn set_zoom(&mut self, zoom: Scalar, min_zoom: Scalar, max_zoom: Scalar) {
+        self.zoom = clamp_zoom(zoom, min_zoom, max_zoom);
+    }
+
+    /// Smoothly transition from this camera to the given `target` over the given number of frames.
+    ///
+    /// E.g. to smoothly jump to a node, pass `camera.focus_on(node_id, &layout)` as the `target`
+    /// and take the next camera from the returned iterator once per frame. The last camera yielded
+    /// is always the `target`.
+    pub fn transition_to(&self, target: Camera, frames: usize) -> CameraTransition {
+        CameraTransition { from: self.clone(), to: target, frames: frames, frame: 0 }
+    }
+}
+
+// Clamp the given zoom to the range between `min_zoom` and `max_zoom`, swapping the bounds if
+// `min_zoom` is greater than `max_zoom`.
+fn clamp_zoom(zoom: Scalar, min_zoom: Scalar, max_zoom: Scalar) -> Scalar {
+    let (min, max) = match min_zoom <= max_zoom {
+        true => (min_zoom, max_zoom),
+        false => (max_zoom, min_zoom),
+    };
+    zoom.max(min).min(max)
+}
+
+impl Iterator for CameraTransition {
+    type Item = Camera;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame >= self.frames {
+            // Ensure a zero-frame transition still arrives at the target.
+            if self.frames == 0 && self.frame == 0 {
+                self.frame = 1;
+                return Some(self.to.clone());
+            }
+            return None;
+        }
+        self.frame += 1;
+        if self.frame == self.frames {
+            return Some(self.to.clone());
+        }
+        let t = self.frame as Scalar / self.frames as Scalar;
+        // Ease in and out so that the jump begins and ends smoothly.
+        let t = t * t * (3.0 - 2.0 * t);
+        Some(self.from.lerp(&self.to, t))
+    }
+}
+
 /// A context for moving through the modes of graph widget instantiation in a type-safe manner.
 ///
-/// The **Session** is shared between 3 stages:
+/// The **Session** is shared between 5 stages:
 ///
 /// 1. **SessionEvents**: Emit all graph events that have occurred since the last instantiation.
 /// 2. **SessionNodes**: Instantiate all node widgets in the graph.
 /// 3. **SessionEdges**: Instantiate all edge widgets in the graph.
+/// 4. **SessionAnnotations**: Instantiate annotations behind the graph's nodes and edges.
+/// 5. **SessionFinal**: Inspect end-of-frame state such as the camera and the graph's bounds.
 ///
-/// NOTE: This should allow for different instantiation orders, e.g: nodes then edges, all
-/// connected components in topo order, edges then nodes, etc.
+/// Alternatively, `SessionEvents::edges_first` instantiates the edges before the nodes via the
+/// **SessionEdgesFirst** and **SessionNodesLast** stages so that edges are drawn beneath nodes.
+///
+/// Each stage is driven by the user via plain iterators rather than callbacks, so application
+/// state (e.g. a reference to the user's model) may simply be borrowed within the loop over
+/// each stage's nodes or edges. As a result, the **Session** types are intentionally not generic
+/// over a user context.
+///
+/// NOTE: This should allow for further instantiation orders, e.g. all connected components in
+/// topo order.
 pub struct Session<NI: NodeId> {
     // The unique identifier used to instantiate the graph widget.
     graph_id: widget::Id,
@@ -314,21 +1290,329 @@ pub struct Session<NI: NodeId> {
     shared: Weak<Mutex<Shared<NI>>>,
 }
 
-/// The first stage of the graph's **Session** event.
-pub struct SessionEvents<NI: NodeId> {
-    session: Session<NI>,
-}
+impl<NI> Session<NI>
+where
+    NI: NodeId,
+{
+    // Produce an iterator yielding the nodes beneath (or above, if `foreground`) the edge layer,
+    // or all nodes if `foreground` is `None`, in order of their layer.
+    fn layered_nodes<'a>(&self, foreground: Option<bool>) -> Nodes<'a, NI> {
+        let graph_id = self.graph_id;
+        let shared = self.shared();
+        let nodes = {
+            let guard = shared.lock().unwrap();
+            let layer = |id: &NI| guard.node_layers.get(id).cloned().unwrap_or(0);
+            let is_foreground = |id: &NI| match guard.edge_layer {
+                Some(edge_layer) => layer(id) > edge_layer,
+                None => false,
+            };
+            let mut nodes = guard.node_ids
+                .iter()
+                // Skip nodes whose first instantiation has been deferred or that have been culled.
+                .filter(|id| !guard.deferred.contains(id) && !guard.culled.contains(id))
+                .filter(|id| foreground.map(|fg| is_foreground(id) == fg).unwrap_or(true))
+                .filter_map(|&id| guard.nodes.get(&id).map(|&NodeInner { point }| (id, point)))
+                .collect::<Vec<_>>();
+            nodes.sort_by_key(|&(id, _)| layer(&id));
+            nodes.into_iter()
+        };
+        Nodes { nodes, shared, graph_id, lifetime: PhantomData }
+    }
 
-/// The second stage of the graph's **Session** event.
-pub struct SessionNodes<NI: NodeId> {
-    session: Session<NI>,
-}
+    // Produce an iterator yielding an `Edge` for each edge for which the given predicate returns
+    // `true`.
+    fn edges_where<'a, F>(&self, predicate: F) -> Edges<'a, NI>
+    where
+        F: Fn(&Shared<NI>, &(NodeSocket<NI>, NodeSocket<NI>)) -> bool,
+    {
+        let graph_id = self.graph_id;
+        let shared = self.shared();
+        let edges = {
+            let guard = shared.lock().unwrap();
+            // Skip edges connected to nodes that are not yielded.
+            let is_skipped = |node_id: &NI| {
+                guard.deferred.contains(node_id) || guard.culled.contains(node_id)
+            };
+            guard.edges
+                .iter()
+                .zip(guard.parallel_edges.iter())
+                .filter(|&(&(start, end), _)| !is_skipped(&start.id) && !is_skipped(&end.id))
+                .filter(|&(edge, _)| predicate(&guard, edge))
+                .map(|(&(start, end), &(index, count))| (start, end, index, count))
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+        Edges { edges, shared, graph_id, lifetime: PhantomData }
+    }
 
-/// The third stage of the graph's **Session** event.
+    // Access the state shared with the `Graph` widget.
+    fn shared(&self) -> Arc<Mutex<Shared<NI>>> {
+        self.shared.upgrade().expect("failed to access `Shared` state")
+    }
+
+    /// The total number of `widget::Id`s currently pooled by the graph across all widget types.
+    ///
+    /// This remains steady as nodes are repeatedly added and removed. See
+    /// `Graph::widget_id_pool_limit`.
+    pub fn widget_id_count(&self) -> usize {
+        self.shared().lock().unwrap().widget_id_map.len()
+    }
+
+    /// The camera through which the graph is currently viewed, including any panning applied by
+    /// the user during this update.
+    ///
+    /// See `Graph::camera`.
+    pub fn camera(&self) -> Camera {
+        self.shared().lock().unwrap().camera.clone()
+    }
+
+    /// Capture the given selection of nodes and the graph's edges between them as a `Subgraph`,
+    /// e.g. for copying and pasting.
+    ///
+    /// Node positions are taken from the given `layout`. See `Subgraph::new`.
+    pub fn subgraph<S>(&self, selection: S, layout: &Layout<NI>) -> Subgraph<NI>
+    where
+        S: IntoIterator<Item=NI>,
+    {
+        let edges = self.shared().lock().unwrap().edges.clone();
+        Subgraph::new(selection, edges, layout)
+    }
+
+    /// The in-progress box selection if the user is currently dragging over the graph's
+    /// background.
+    ///
+    /// The `Rect` is described in the same space as node positions within the `Layout`, i.e.
+    /// relative to the centre of the graph and offset by the camera's position. To draw it over
+    /// the graph, position it at its centre minus the camera's position relative to the graph.
+    pub fn box_selection_rect(&self) -> Option<Rect> {
+        self.shared().lock().unwrap().box_selection
+    }
+
+    /// Whether or not the user is currently interacting with the graph.
+    ///
+    /// This is `true` while a node is being dragged, while an edge is being created (including
+    /// between the two clicks of `ConnectMode::ClickClick`), while a box selection is in progress
+    /// or during an update in which the camera was panned via the arrow keys.
+    pub fn is_interacting(&self) -> bool {
+        let shared = self.shared();
+        let shared = shared.lock().unwrap();
+        shared.pressing_node
+            || shared.connecting.is_some()
+            || shared.box_selection.is_some()
+            || shared.panned
+    }
+
+    /// The set of colors given to the graph via `Graph::colors`, if any.
+    pub fn colors(&self) -> Option<GraphColors> {
+        self.shared().lock().unwrap().colors
+    }
+
+    /// The node currently under the mouse, if any.
+    ///
+    /// This is only known once the node has been instantiated during the **SessionNodes** stage.
+    pub fn hovered_node(&self) -> Option<NI> {
+        self.shared().lock().unwrap().hovered_node
+    }
+
+    /// The edge beneath the node currently being dragged, if any.
+    ///
+    /// A node is considered to be over an edge when its centre lies within the
+    /// `EDGE_DROP_TOLERANCE` of the straight line between the edge's sockets. Edges connected to
+    /// the dragged node are ignored. If the node is dropped while over an edge, an
+    /// `EdgeEvent::SplitRequested` is emitted.
+    pub fn edge_under_drag(&self) -> Option<(NodeSocket<NI>, NodeSocket<NI>)> {
+        self.shared().lock().unwrap().edge_under_drag.map(|(_, edge)| edge)
+    }
+
+    /// The socket under the mouse while no mouse button is pressed, if any.
+    ///
+    /// Sockets are found using the node widgets from the previous update.
+    pub fn hovered_socket(&self) -> Option<(SocketType, NodeSocket<NI>)> {
+        self.shared().lock().unwrap().hovered_socket.map(|(ty, socket, _)| (ty, socket))
+    }
+
+    /// The socket from which an edge is currently being created, if any.
+    ///
+    /// This may be used to draw a preview of the edge from the socket to the mouse.
+    pub fn edge_start(&self) -> Option<(SocketType, NodeSocket<NI>)> {
+        self.shared().lock().unwrap().connecting
+    }
+
+    /// Whether or not the given socket is a valid target for the edge currently being created.
+    ///
+    /// While an edge is being created, the sockets of the opposite type within the
+    /// `DROP_TARGET_RADIUS` of the mouse that are accepted by the validator given to
+    /// `Graph::validate_connection` are considered drop targets. These may be drawn in an accent
+    /// color to indicate where the edge may be connected.
+    pub fn is_drop_target(&self, socket_type: SocketType, socket: NodeSocket<NI>) -> bool {
+        self.shared().lock().unwrap().drop_targets.contains(&(socket_type, socket))
+    }
+
+    /// The socket nearest to the given absolute position, ignoring sockets whose rectangles are
+    /// further than `radius` away.
+    ///
+    /// The position is in the same coordinates as the mouse (e.g. `ui.global_input()`), so the
+    /// `Camera` transform is already accounted for by the positions of the node widgets. A socket
+    /// whose rectangle contains the position is always considered nearest. As with edge creation,
+    /// sockets are found using the node widgets from the previous update.
+    ///
+    /// The `SocketType` describes whether the `NodeSocket` refers to an input or an output.
+    pub fn socket_at_point(&self, point: Point, radius: Scalar)
+        -> Option<(SocketType, NodeSocket<NI>)>
+    {
+        self.shared().lock().unwrap().socket_grid.nearest_socket(point, radius, None)
+    }
+
+    /// Whether or not the graph yields an edge equal to the edge from `start` to `end`, as
+    /// described by the given `directionality`.
+    ///
+    /// This may be used to avoid adding duplicate edges to the user's graph. See also
+    /// `Graph::reject_duplicate_edges`.
+    pub fn contains_edge(&self, start: NodeSocket<NI>, end: NodeSocket<NI>,
+                         directionality: Directionality) -> bool
+    {
+        let shared = self.shared();
+        let shared = shared.lock().unwrap();
+        is_duplicate(&shared.edges, (start, end), Some(directionality))
+    }
+
+    /// Whether or not the graph's edges are directed, as specified via `Graph::directed`.
+    pub fn is_directed(&self) -> bool {
+        self.shared().lock().unwrap().directed
+    }
+
+    /// All edges yielded to the graph that start or end at the given node, in the order in which
+    /// they were yielded.
+    ///
+    /// Each pair is directed as it was yielded, i.e. `(start, end)` where `start` is an output
+    /// socket and `end` is an input socket, regardless of which of the two belongs to the given
+    /// node. An edge from the node to itself is returned once.
+    ///
+    /// This may be used to remove a node's edges from the user's graph along with the node.
+    pub fn incident_edges(&self, node_id: NI) -> Vec<(NodeSocket<NI>, NodeSocket<NI>)> {
+        let shared = self.shared();
+        let shared = shared.lock().unwrap();
+        shared.edges
+            .iter()
+            .filter(|&&(start, end)| start.id == node_id || end.id == node_id)
+            .cloned()
+            .collect()
+    }
+
+    /// The socket under the mouse to which the edge being created may not be connected, if any.
+    ///
+    /// This is the case when the socket is not the socket from which the edge began and is not a
+    /// drop target, e.g. because it is of the same type as the start socket, because the
+    /// validator given to `Graph::validate_connection` rejects the connection or because the
+    /// connection would duplicate an existing edge. The graph draws a red cross at the mouse while
+    /// this is `Some`.
+    pub fn invalid_target(&self) -> Option<(SocketType, NodeSocket<NI>)> {
+        self.shared().lock().unwrap().invalid_target
+    }
+
+    /// The output and input sockets of the connection rejected by the validator given to
+    /// `Graph::validate_connection` during this update, if any.
+    pub fn rejected_connection(&self) -> Option<(NodeSocket<NI>, NodeSocket<NI>)> {
+        self.shared().lock().unwrap().rejected_connection
+    }
+
+    /// The edge whose end the user has grabbed from its input socket for reconnecting, if any.
+    ///
+    /// While reconnecting, `edge_start` returns the start of this edge. This may be used to hide
+    /// the edge while its end is being dragged.
+    pub fn edge_being_reconnected(&self) -> Option<(NodeSocket<NI>, NodeSocket<NI>)> {
+        self.shared().lock().unwrap().reconnecting
+    }
+
+    /// Begin creating an edge from the given socket as though the user had pressed it.
+    ///
+    /// An `EdgeEvent::AddStart` is emitted during the next **SessionEvents** stage. The edge is
+    /// then completed or cancelled by the user's next left mouse button release when using
+    /// `ConnectMode::PressDrag`, or their next left mouse button press when using
+    /// `ConnectMode::ClickClick`.
+    pub fn begin_edge(&self, socket_type: SocketType, socket: NodeSocket<NI>) {
+        let shared = self.shared();
+        let mut shared = shared.lock().unwrap();
+        shared.connecting = Some((socket_type, socket));
+        shared.connecting_by_press = false;
+        shared.reconnecting = None;
+        shared.events.push_back(Event::Edge(EdgeEvent::AddStart(socket)));
+    }
+
+    /// The vector from the centre of socket `a` to the centre of socket `b`.
+    ///
+    /// As with edges, `a` is assumed to be an output socket and `b` an input socket.
+    ///
+    /// Returns `None` if either socket's node has not yet been instantiated during this session.
+    pub fn socket_vector(&self, a: NodeSocket<NI>, b: NodeSocket<NI>, ui: &Ui) -> Option<Point> {
+        let shared = self.shared();
+        let shared = shared.lock().unwrap();
+        let node_widget_ids = &shared.widget_id_map.node_widget_ids;
+        let a_id = match node_widget_ids.get(&a.id) { Some(&id) => id, None => return None };
+        let b_id = match node_widget_ids.get(&b.id) { Some(&id) => id, None => return None };
+        let a_rect = node::socket_rect(a_id, SocketType::Output, a.socket_index, ui);
+        let b_rect = node::socket_rect(b_id, SocketType::Input, b.socket_index, ui);
+        match (a_rect, b_rect) {
+            (Some(a), Some(b)) => Some([b.x() - a.x(), b.y() - a.y()]),
+            _ => None,
+        }
+    }
+
+    /// The distance between the centre of socket `a` and the centre of socket `b`.
+    ///
+    /// See `socket_vector` for details.
+    pub fn socket_distance(&self, a: NodeSocket<NI>, b: NodeSocket<NI>, ui: &Ui) -> Option<Scalar> {
+        self.socket_vector(a, b, ui).map(|v| (v[0] * v[0] + v[1] * v[1]).sqrt())
+    }
+
+    /// All pairs of sockets whose rectangles overlap on their node.
+    ///
+    /// Clicks over overlapping sockets are ambiguous, so a non-empty result usually indicates
+    /// that a node is too small for its number of sockets and should be enlarged. Overlaps are
+    /// detected using the node widgets from the previous update.
+    pub fn socket_overlaps(&self) -> Vec<(SocketOverlap<NI>, SocketOverlap<NI>)> {
+        self.shared().lock().unwrap().socket_overlaps.clone()
+    }
+}
+
+/// The first stage of the graph's **Session** event.
+pub struct SessionEvents<NI: NodeId> {
+    session: Session<NI>,
+}
+
+/// The second stage of the graph's **Session** event.
+pub struct SessionNodes<NI: NodeId> {
+    session: Session<NI>,
+}
+
+/// The third stage of the graph's **Session** event.
 pub struct SessionEdges<NI: NodeId> {
     session: Session<NI>,
 }
 
+/// The second stage of the graph's **Session** event when instantiating edges before nodes.
+///
+/// See `SessionEvents::edges_first`.
+pub struct SessionEdgesFirst<NI: NodeId> {
+    session: Session<NI>,
+}
+
+/// The third stage of the graph's **Session** event when instantiating edges before nodes.
+pub struct SessionNodesLast<NI: NodeId> {
+    session: Session<NI>,
+}
+
+/// The fourth stage of the graph's **Session** event, used for instantiating annotations.
+pub struct SessionAnnotations<NI: NodeId> {
+    session: Session<NI>,
+}
+
+/// The fifth and final stage of the graph's **Session** event, exposing end-of-frame state such as
+/// the camera and the bounds of the graph, e.g. for drawing an info overlay.
+pub struct SessionFinal<NI: NodeId> {
+    session: Session<NI>,
+}
+
 /// An iterator yielding all pending events.
 pub struct Events<'a, NI: NodeId> {
     shared: Arc<Mutex<Shared<NI>>>,
@@ -340,8 +1624,9 @@ pub struct Events<'a, NI: NodeId> {
 ///
 /// Each `NodeContext` can be used for instantiating a widget for each node in the graph.
 pub struct Nodes<'a, NI: 'a + NodeId> {
-    // Index into the `node_ids`, indicating which node we're up to.
-    index: usize,
+    // A snapshot of the nodes to be yielded, taken under a single lock when the iterator is
+    // produced. The `shared` state is only locked again for `WidgetIdMap` access.
+    nodes: ::std::vec::IntoIter<(NI, Point)>,
     shared: Arc<Mutex<Shared<NI>>>,
     // The `widget::Id` of the parent graph widget.
     graph_id: widget::Id,
@@ -381,14 +1666,41 @@ pub struct NodeWidget<'a, NI: 'a + NodeId, W> {
     widget: W,
     // `None` if not yet requested the `WidgetIdMap`. `Some` if it has.
     widget_id: Cell<Option<widget::Id>>,
+    // The `widget::Id` pinned by the user via `with_widget_id`, if any.
+    pinned_widget_id: Option<widget::Id>,
+    // The layer to which the node is assigned.
+    layer: i8,
+}
+
+/// A `NodeWidget` for one of two widget types, allowing a `match` on the kind of a node to choose
+/// between widget types while producing a single value.
+///
+/// Each variant draws its `widget::Id` from the pool for its own widget type, so nodes keep their
+/// `widget::Id`s across updates as long as their kind does not change. Graphs with more than two
+/// kinds of node may instead `set` the `NodeWidget` within each arm of the `match`.
+pub enum AnyNodeWidget<'a, NI: 'a + NodeId, A, B> {
+    /// The node uses the first widget type.
+    A(NodeWidget<'a, NI, A>),
+    /// The node uses the second widget type.
+    B(NodeWidget<'a, NI, B>),
+}
+
+/// The event produced by setting an `AnyNodeWidget`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AnyNodeEvent<A, B> {
+    /// The event produced by the first widget type.
+    A(A),
+    /// The event produced by the second widget type.
+    B(B),
 }
 
 /// An iterator-like type yielding a `NodeContext` for every node in the graph.
 ///
 /// Each `NodeContext` can be used for instantiating a widget for each node in the graph.
 pub struct Edges<'a, NI: 'a + NodeId> {
-    // The index into the `shared.edges` `Vec` that for the next `Edge` that is to be yielded.
-    index: usize,
+    // A snapshot of the edges to be yielded along with their parallel index and count, taken under
+    // a single lock when the iterator is produced.
+    edges: ::std::vec::IntoIter<EdgeSnapshot<NI>>,
     shared: Arc<Mutex<Shared<NI>>>,
     // The `widget::Id` of the parent graph widget.
     graph_id: widget::Id,
@@ -396,6 +1708,9 @@ pub struct Edges<'a, NI: 'a + NodeId> {
     lifetime: PhantomData<&'a ()>,
 }
 
+// The start, end, parallel index and parallel count of an edge yielded by `Edges`.
+type EdgeSnapshot<NI> = (NodeSocket<NI>, NodeSocket<NI>, usize, usize);
+
 /// A context for an edge yielded during the edge instantiation stage.
 ///
 /// Tyis type can 
@@ -408,10 +1723,17 @@ pub struct Edge<'a, NI: NodeId> {
     start: NodeSocket<NI>,
     // The end of the edge.
     end: NodeSocket<NI>,
+    // The index of the edge among those sharing its start and end nodes.
+    parallel_index: usize,
+    // The number of edges sharing the edge's start and end nodes.
+    parallel_count: usize,
     // Bind the lifetime to the `SessionEdges` so the user can't leak the `Shared` state.
     lifetime: PhantomData<&'a ()>,
 }
 
+/// The spacing between the curves of parallel edges used by `Edge::bezier`.
+pub const PARALLEL_EDGE_SPACING: Scalar = 20.0;
+
 /// Returned when an `Edge` is assigned a widget.
 ///
 /// This intermediary type allows for accessing the `widget::Id` before the widget itself is
@@ -423,19 +1745,165 @@ pub struct EdgeWidget<'a, NI: 'a + NodeId, W> {
     widget_id: Cell<Option<widget::Id>>,
 }
 
-// impl<NI> Layout<NI>
-// where
-//     NI: Eq + Hash,
-// {
-//     /// The position of the node at the given node identifier.
-//     pub fn get(&self, node_id: NI) -> Option<&Point> {
-//         self.map.get(&node_id)
-//     }
-//     /// The position of the node at the given node identifier.
-//     pub fn get_mut(&mut self, node_id: NI) -> Option<&mut Point> {
-//         self.map.get_mut(&node_id)
-//     }
-// }
+impl<'a, NI> EdgeWidget<'a, NI, widget::Line>
+where
+    NI: NodeId,
+{
+    /// Set the thickness of the line.
+    pub fn thickness(self, thickness: Scalar) -> Self {
+        self.map(|line| line.thickness(thickness))
+    }
+}
+
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        EdgeStyle {
+            thickness: DEFAULT_EDGE_THICKNESS,
+            color: None,
+            cap: None,
+        }
+    }
+}
+
+impl Default for EdgeLabelStyle {
+    fn default() -> Self {
+        EdgeLabelStyle {
+            color: color::WHITE,
+            font_size: 12,
+            background_color: Some(color::rgba(0.0, 0.0, 0.0, 0.7)),
+            padding: 4.0,
+        }
+    }
+}
+
+impl GraphColors {
+    /// Colors suited to a light background.
+    pub fn light() -> Self {
+        GraphColors {
+            background: color::rgb(0.93, 0.93, 0.94),
+            node: color::WHITE,
+            node_border: color::LIGHT_CHARCOAL,
+            socket: color::GREY,
+            edge: color::CHARCOAL,
+        }
+    }
+
+    /// Colors suited to a dark background.
+    ///
+    /// The node and edge colors match the defaults used by `node::Node` and the `Edge` methods.
+    pub fn dark() -> Self {
+        GraphColors {
+            background: color::rgb(0.1, 0.11, 0.13),
+            node: color::TRANSPARENT,
+            node_border: color::DARK_CHARCOAL,
+            socket: color::DARK_GREY,
+            edge: color::DARK_CHARCOAL,
+        }
+    }
+}
+
+impl<NI> Layout<NI>
+where
+    NI: NodeId,
+{
+    /// The position of the node at the given node identifier.
+    pub fn get(&self, node_id: NI) -> Option<&Point> {
+        self.map.get(&node_id)
+    }
+
+    /// A mutable reference to the position of the node at the given node identifier.
+    pub fn get_mut(&mut self, node_id: NI) -> Option<&mut Point> {
+        self.map.get_mut(&node_id)
+    }
+
+    /// Set the position of the node at the given node identifier.
+    ///
+    /// Returns the previous position of the node if there was one.
+    pub fn insert(&mut self, node_id: NI, point: Point) -> Option<Point> {
+        self.map.insert(node_id, point)
+    }
+
+    /// Remove the node at the given node identifier from the layout.
+    ///
+    /// Returns the position of the node if there was one.
+    pub fn remove(&mut self, node_id: NI) -> Option<Point> {
+        self.map.remove(&node_id)
+    }
+
+    /// Produce a **Layout** from node positions stored in single precision.
+    ///
+    /// The positions are converted in a single pass as they are collected.
+    pub fn from_f32<I>(positions: I) -> Self
+    where
+        I: IntoIterator<Item=(NI, [f32; 2])>,
+    {
+        let mut layout = Layout { map: HashMap::new() };
+        layout.extend_f32(positions);
+        layout
+    }
+
+    /// Set the positions of the given nodes from positions stored in single precision.
+    ///
+    /// This is useful for applications that store their node positions as `f32` and wish to feed
+    /// them to the **Layout** in bulk once per frame, rather than casting each position at the
+    /// point of use.
+    pub fn extend_f32<I>(&mut self, positions: I)
+    where
+        I: IntoIterator<Item=(NI, [f32; 2])>,
+    {
+        let positions = positions.into_iter();
+        self.map.reserve(positions.size_hint().0);
+        let positions = positions.map(|(id, p)| (id, [p[0] as Scalar, p[1] as Scalar]));
+        self.map.extend(positions);
+    }
+
+    /// Convert the position of every node to single precision.
+    ///
+    /// Allows for writing positions modified by the **Graph** (e.g. via dragging) back to an
+    /// application's `f32` storage in bulk.
+    pub fn to_f32(&self) -> HashMap<NI, [f32; 2]> {
+        self.map.iter().map(|(&id, p)| (id, [p[0] as f32, p[1] as f32])).collect()
+    }
+
+    /// An iterator yielding the position of every node ordered by node identifier.
+    ///
+    /// Unlike iterating over the underlying `HashMap`, the order is the same for every **Layout**
+    /// with the same nodes, which is useful for reproducible rendering and serialization.
+    pub fn iter_sorted(&self) -> ::std::vec::IntoIter<(NI, Point)>
+    where
+        NI: Ord,
+    {
+        self.iter_sorted_by_key(|node_id| node_id)
+    }
+
+    /// An iterator yielding the position of every node ordered by the key produced for each node
+    /// identifier by the given function.
+    ///
+    /// The order of nodes with equal keys is unspecified, so keys should be unique where a
+    /// deterministic order is required.
+    pub fn iter_sorted_by_key<K, F>(&self, mut key: F) -> ::std::vec::IntoIter<(NI, Point)>
+    where
+        K: Ord,
+        F: FnMut(NI) -> K,
+    {
+        let mut positions: Vec<_> = self.map.iter().map(|(&id, &p)| (id, p)).collect();
+        positions.sort_by_key(|&(id, _)| key(id));
+        positions.into_iter()
+    }
+}
+
+impl<'a, NI> Deref for LayoutRef<'a, NI>
+where
+    NI: NodeId,
+{
+    type Target = Layout<NI>;
+    fn deref(&self) -> &Self::Target {
+        match *self {
+            LayoutRef::Ref(layout) => layout,
+            LayoutRef::Mut(ref layout) => layout,
+        }
+    }
+}
 
 impl<NI> From<HashMap<NI, Point>> for Layout<NI>
 where
@@ -446,6 +1914,18 @@ where
     }
 }
 
+impl<NI> Extend<(NI, Point)> for Layout<NI>
+where
+    NI: NodeId,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item=(NI, Point)>,
+    {
+        self.map.extend(iter)
+    }
+}
+
 impl<NI> Into<HashMap<NI, Point>> for Layout<NI>
 where
     NI: NodeId,
@@ -456,6 +1936,76 @@ where
     }
 }
 
+impl<NI> Deref for SessionEvents<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl<NI> Deref for SessionNodes<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl<NI> Deref for SessionEdges<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl<NI> Deref for SessionEdgesFirst<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl<NI> Deref for SessionNodesLast<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl<NI> Deref for SessionAnnotations<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
+impl<NI> Deref for SessionFinal<NI>
+where
+    NI: NodeId,
+{
+    type Target = Session<NI>;
+    fn deref(&self) -> &Self::Target {
+        &self.session
+    }
+}
+
 impl<NI> SessionEvents<NI>
 where
     NI: NodeId,
@@ -466,11 +2016,50 @@ where
         Events { shared, lifetime: PhantomData }
     }
 
+    /// Whether or not there are no pending events.
+    ///
+    /// Unlike iterating over `events`, this does not consume any events, so it may be used to
+    /// return early from updates in which nothing has happened.
+    pub fn is_empty(&self) -> bool {
+        self.shared().lock().unwrap().events.is_empty()
+    }
+
     /// Transition from the **SessionEvents** into **SessionNodes** for instantiating nodes.
     pub fn next(self) -> SessionNodes<NI> {
         let SessionEvents { session } = self;
         SessionNodes { session }
     }
+
+    /// Transition from the **SessionEvents** into **SessionEdgesFirst**, instantiating the edges
+    /// before the nodes so that the edges are drawn beneath them.
+    ///
+    /// As the node widgets are not yet instantiated for this update, the socket positions used by
+    /// the `Edge` methods fall back to the centre of each node's position within the `Layout`.
+    pub fn edges_first(self) -> SessionEdgesFirst<NI> {
+        let SessionEvents { session } = self;
+        SessionEdgesFirst { session }
+    }
+}
+
+impl<'a, NI> Events<'a, NI>
+where
+    NI: NodeId,
+{
+    /// Take all pending events from the queue under a single lock.
+    ///
+    /// Iterating over `Events` directly locks the `Shared` state once per event.
+    pub fn drain(self) -> ::std::collections::vec_deque::IntoIter<Event<NI>> {
+        let events = match self.shared.lock() {
+            Ok(mut guard) => ::std::mem::replace(&mut guard.events, VecDeque::new()),
+            Err(_) => VecDeque::new(),
+        };
+        events.into_iter()
+    }
+
+    /// Collect all pending events into a `Vec` under a single lock.
+    pub fn collect_vec(self) -> Vec<Event<NI>> {
+        self.drain().collect()
+    }
 }
 
 impl<'a, NI> Iterator for Events<'a, NI>
@@ -490,10 +2079,11 @@ where
     NI: NodeId,
 {
     /// Produce an iterator yielding a `NodeContext` for each node present in the graph.
+    ///
+    /// If an edge layer was specified via `Graph::edge_layer`, only the nodes at or beneath it are
+    /// yielded. Nodes are yielded in order of their layer, lowest first.
     pub fn nodes(&mut self) -> Nodes<NI> {
-        let graph_id = self.session.graph_id;
-        let shared = self.session.shared.upgrade().expect("failed to access `Shared` state");
-        Nodes { index: 0, shared, graph_id, lifetime: PhantomData }
+        self.session.layered_nodes(Some(false))
     }
 
     /// Transition from the **SessionNodes** into **SessionEdges** for instantiating edges.
@@ -509,24 +2099,13 @@ where
 {
     type Item = NodeContext<'a, NI>;
     fn next(&mut self) -> Option<Self::Item> {
-        let index = self.index;
-        self.index += 1;
-        self.shared.lock()
-            .ok()
-            .and_then(|guard| {
-                guard.node_ids
-                    .get(index)
-                    .and_then(|&id| guard.nodes.get(&id).map(|&inner| (id, inner)))
-            })
-            .map(|(node_id, NodeInner { point })| {
-                NodeContext {
-                    node_id,
-                    point,
-                    graph_id: self.graph_id,
-                    shared: self.shared.clone(),
-                    lifetime: PhantomData,
-                }
-            })
+        self.nodes.next().map(|(node_id, point)| NodeContext {
+            node_id,
+            point,
+            graph_id: self.graph_id,
+            shared: self.shared.clone(),
+            lifetime: PhantomData,
+        })
     }
 }
 
@@ -536,9 +2115,202 @@ where
 {
     /// Produce an iterator yielding an `Edge` for each node present in the graph.
     pub fn edges(&mut self) -> Edges<NI> {
-        let graph_id = self.session.graph_id;
-        let shared = self.session.shared.upgrade().expect("failed to access `Shared` state");
-        Edges { index: 0, shared, graph_id, lifetime: PhantomData }
+        self.session.edges_where(|_, _| true)
+    }
+
+    /// The rectangle of the given node's widget, as returned by `node::node_rect`.
+    ///
+    /// Returns `None` if the node has no widget or if its widget is not a `node::Node`.
+    pub fn node_rect(&self, node_id: NI, ui: &Ui) -> Option<Rect> {
+        node_rect(node_id, self.session.graph_id, ui)
+    }
+
+    /// Produce an iterator yielding a `NodeContext` for each node above the edge layer specified
+    /// via `Graph::edge_layer`.
+    ///
+    /// Nodes instantiated here are drawn above the edges. Nodes are yielded in order of their
+    /// layer, lowest first.
+    pub fn foreground_nodes(&mut self) -> Nodes<NI> {
+        self.session.layered_nodes(Some(true))
+    }
+
+    /// Transition from the **SessionEdges** into **SessionAnnotations** for instantiating
+    /// annotations.
+    pub fn next(self) -> SessionAnnotations<NI> {
+        let SessionEdges { session } = self;
+        SessionAnnotations { session }
+    }
+
+    /// Produce an iterator yielding an `Edge` for each edge with at least one end at a node
+    /// within the given selection.
+    ///
+    /// This allows the edges of the user's selection of nodes (e.g. those within a box selection)
+    /// to be highlighted or moved along with the selected subgraph in a single pass.
+    pub fn edges_incident_to<S>(&mut self, selection: S) -> Edges<NI>
+    where
+        S: IntoIterator<Item=NI>,
+    {
+        let selection: HashSet<NI> = selection.into_iter().collect();
+        self.session.edges_where(move |_, &(start, end)| {
+            selection.contains(&start.id) || selection.contains(&end.id)
+        })
+    }
+
+    /// Produce an iterator yielding an `Edge` for each edge that starts or ends at the node
+    /// currently under the mouse.
+    ///
+    /// This is the same set of edges for which `Edge::is_incident_to_hovered` returns `true`,
+    /// allowing the edges of the hovered node to be highlighted in a single pass.
+    pub fn edges_incident_to_hovered(&mut self) -> Edges<NI> {
+        self.session.edges_where(|shared, &(start, end)| match shared.hovered_node {
+            Some(node_id) => start.id == node_id || end.id == node_id,
+            None => false,
+        })
+    }
+}
+
+impl<NI> SessionEdgesFirst<NI>
+where
+    NI: NodeId,
+{
+    /// Produce an iterator yielding an `Edge` for each edge present in the graph.
+    ///
+    /// See `SessionEvents::edges_first` for how edges are positioned during this stage.
+    pub fn edges(&mut self) -> Edges<NI> {
+        self.session.edges_where(|_, _| true)
+    }
+
+    /// Transition from the **SessionEdgesFirst** into **SessionNodesLast** for instantiating
+    /// nodes.
+    pub fn next(self) -> SessionNodesLast<NI> {
+        let SessionEdgesFirst { session } = self;
+        SessionNodesLast { session }
+    }
+}
+
+impl<NI> SessionNodesLast<NI>
+where
+    NI: NodeId,
+{
+    /// Produce an iterator yielding a `NodeContext` for each node present in the graph.
+    ///
+    /// As all edges have already been instantiated, any edge layer specified via
+    /// `Graph::edge_layer` is ignored. Nodes are yielded in order of their layer, lowest first.
+    pub fn nodes(&mut self) -> Nodes<NI> {
+        self.session.layered_nodes(None)
+    }
+
+    /// Transition from the **SessionNodesLast** into **SessionAnnotations** for instantiating
+    /// annotations.
+    pub fn next(self) -> SessionAnnotations<NI> {
+        let SessionNodesLast { session } = self;
+        SessionAnnotations { session }
+    }
+}
+
+impl<NI> SessionAnnotations<NI>
+where
+    NI: NodeId,
+{
+    /// Instantiate the given annotation over the given rectangle.
+    ///
+    /// As with node positions, the `rect` is relative to the centre of the graph. Annotations are
+    /// instantiated at the `annotation::DEPTH` so that they are drawn behind the nodes and edges.
+    ///
+    /// The user is responsible for storing the rectangle of each annotation and for applying the
+    /// returned `annotation::Event`s to it. If the annotation is moved and
+    /// `Annotation::move_contained_nodes` is enabled, a `NodeEvent::Dragged` is emitted for each
+    /// node whose position lies within the `rect`. These are yielded during the next
+    /// **SessionEvents** stage.
+    pub fn set(&mut self, annotation: Annotation, rect: Rect, ui: &mut UiCell)
+        -> Option<annotation::Event>
+    {
+        let graph_id = self.session.graph_id;
+        let shared = self.session.shared();
+        let (widget_id, camera_point) = {
+            let mut shared = shared.lock().unwrap();
+            let widget_id = shared.widget_id_map
+                .next_id_for_annotation::<Annotation>(&mut ui.widget_id_generator());
+            (widget_id, shared.camera.point)
+        };
+        let move_contained_nodes = annotation.move_contained_nodes;
+        let xy = [rect.x() - camera_point[0], rect.y() - camera_point[1]];
+        let event = annotation
+            .xy_relative_to(graph_id, xy)
+            .wh(rect.dim())
+            .depth(annotation::DEPTH)
+            .parent(graph_id)
+            .set(widget_id, ui);
+
+        if let Some(annotation::Event::Moved([dx, dy])) = event {
+            if move_contained_nodes {
+                let mut shared = shared.lock().unwrap();
+                let Shared { ref nodes, ref mut node_points, ref mut events, .. } = *shared;
+                for (&node_id, node) in nodes {
+                    if !rect.is_over(node.point) {
+                        continue;
+                    }
+                    let from = node.point;
+                    let to = [from[0] + dx, from[1] + dy];
+                    // Keep the node in place if the user's `Layout` is not updated.
+                    if let Some(&mut (_, ref mut point)) = node_points.get_mut(&node_id) {
+                        *point = to;
+                    }
+                    events.push_back(Event::Node(NodeEvent::Dragged { node_id, from, to }));
+                }
+            }
+        }
+
+        event
+    }
+
+    /// Transition from the **SessionAnnotations** into the **SessionFinal** stage.
+    pub fn next(self) -> SessionFinal<NI> {
+        let SessionAnnotations { session } = self;
+        SessionFinal { session }
+    }
+}
+
+impl<NI> SessionFinal<NI>
+where
+    NI: NodeId,
+{
+    /// The position of the camera relative to the centre of the graph.
+    ///
+    /// See `Session::camera`.
+    pub fn camera_point(&self) -> Point {
+        self.camera().point()
+    }
+
+    /// The zoom of the camera, where `1.0` is the original resolution.
+    ///
+    /// This may be multiplied by `100.0` to display the zoom as a percentage.
+    pub fn zoom(&self) -> Scalar {
+        self.camera().zoom()
+    }
+
+    /// The smallest rectangle containing all nodes, relative to the centre of the graph.
+    ///
+    /// Each node is described by its position and the dimensions of its widget as of its last
+    /// instantiation, so nodes that have never been instantiated are described by their position
+    /// alone. Returns `None` if the graph has no nodes.
+    pub fn bounds(&self) -> Option<Rect> {
+        let shared = self.shared();
+        let shared = shared.lock().unwrap();
+        shared.nodes
+            .iter()
+            .map(|(node_id, node)| {
+                let dim = shared.node_dims.get(node_id).cloned().unwrap_or([0.0; 2]);
+                Rect::from_xy_dim(node.point, dim)
+            })
+            .fold(None, |bounds: Option<Rect>, rect| match bounds {
+                None => Some(rect),
+                Some(bounds) => {
+                    let (l, r, b, t) = bounds.l_r_b_t();
+                    let (rl, rr, rb, rt) = rect.l_r_b_t();
+                    Some(Rect::from_corners([l.min(rl), b.min(rb)], [r.max(rr), t.max(rt)]))
+                },
+            })
     }
 }
 
@@ -548,21 +2320,15 @@ where
 {
     type Item = Edge<'a, NI>;
     fn next(&mut self) -> Option<Self::Item> {
-        let index = self.index;
-        self.index += 1;
-        self.shared.lock()
-            .ok()
-            .and_then(|guard| {
-                guard.edges.get(index).map(|&(start, end)| {
-                    Edge {
-                        graph_id: self.graph_id,
-                        shared: self.shared.clone(),
-                        start: start,
-                        end: end,
-                        lifetime: PhantomData,
-                    }
-                })
-            })
+        self.edges.next().map(|(start, end, parallel_index, parallel_count)| Edge {
+            graph_id: self.graph_id,
+            shared: self.shared.clone(),
+            start: start,
+            end: end,
+            parallel_index,
+            parallel_count,
+            lifetime: PhantomData,
+        })
     }
 }
 
@@ -580,13 +2346,71 @@ where
         self.point
     }
 
+    /// Whether or not the socket of the given type at the given index on this node is a valid
+    /// target for the edge currently being created.
+    ///
+    /// See `Session::is_drop_target`.
+    pub fn is_drop_target(&self, socket_type: SocketType, socket_index: usize) -> bool {
+        let socket = NodeSocket { id: self.node_id, socket_index };
+        self.shared.lock().unwrap().drop_targets.contains(&(socket_type, socket))
+    }
+
+    /// The number of updates since the node first appeared within the graph's nodes, or `None` if
+    /// the node has been present since the graph's first update.
+    ///
+    /// This is `Some(0)` during the update in which the node appears. See `Node::appear_duration`.
+    pub fn age(&self) -> Option<u64> {
+        let shared = self.shared.lock().unwrap();
+        shared.node_appearances.get(&self.node_id).map(|&appeared| shared.update_count - appeared)
+    }
+
     /// Specify the widget to use 
     pub fn widget<W>(self, widget: W) -> NodeWidget<'a, NI, W> {
         NodeWidget {
             node: self,
             widget,
             widget_id: Cell::new(None),
+            pinned_widget_id: None,
+            layer: 0,
+        }
+    }
+
+    /// Use the given widget of the first of two widget types for this node.
+    ///
+    /// See `AnyNodeWidget` for choosing between widget types for different kinds of node.
+    pub fn widget_a<A, B>(self, widget: A) -> AnyNodeWidget<'a, NI, A, B> {
+        AnyNodeWidget::A(self.widget(widget))
+    }
+
+    /// Use the given widget of the second of two widget types for this node.
+    ///
+    /// See `AnyNodeWidget` for choosing between widget types for different kinds of node.
+    pub fn widget_b<A, B>(self, widget: B) -> AnyNodeWidget<'a, NI, A, B> {
+        AnyNodeWidget::B(self.widget(widget))
+    }
+
+    /// Wrap the given widget in a `node::Node` with the given number of sockets and use it as
+    /// the widget for this node.
+    ///
+    /// The `node::Node` is styled with the graph's default node style, set via
+    /// `Graph::node_style`. Where the default node style does not specify a socket layout, the
+    /// layout given to `Graph::input_socket_layout` or `Graph::output_socket_layout` is used. The
+    /// node is given the zoom of the graph's camera for its `Node::lod_threshold` and the node's
+    /// `age` for its `Node::appear_duration`.
+    pub fn styled_widget<W>(self, widget: W, inputs: usize, outputs: usize)
+        -> NodeWidget<'a, NI, Node<W>>
+    {
+        let mut node = Node::new(widget).inputs(inputs).outputs(outputs);
+        node.age = self.age();
+        {
+            let shared = self.shared.lock().unwrap();
+            let SocketLayouts { input, output } = shared.socket_layouts;
+            node.style = shared.node_style;
+            node.style.input_socket_layout = node.style.input_socket_layout.or(Some(input));
+            node.style.output_socket_layout = node.style.output_socket_layout.or(Some(output));
+            node.zoom = shared.camera.zoom;
         }
+        self.widget(node)
     }
 }
 
@@ -600,10 +2424,14 @@ where
         match self.widget_id.get() {
             Some(id) => id,
             None => {
-                // Request a `widget::Id` from the `WidgetIdMap`.
+                // Use the pinned `widget::Id` if there is one, otherwise request one from the
+                // `WidgetIdMap`.
                 let mut shared = self.node.shared.lock().unwrap();
-                let id = shared.widget_id_map
-                    .next_id_for_node::<W>(self.node_id, &mut ui.widget_id_generator());
+                let id_gen = &mut ui.widget_id_generator();
+                let id = match self.pinned_widget_id {
+                    Some(id) => shared.widget_id_map.pin_id_for_node::<W>(self.node_id, id, id_gen),
+                    None => shared.widget_id_map.next_id_for_node::<W>(self.node_id, id_gen),
+                };
                 self.widget_id.set(Some(id));
                 id
             },
@@ -615,19 +2443,59 @@ where
     where
         M: FnOnce(W) -> W,
     {
-        let NodeWidget { node, mut widget, widget_id } = self;
+        let NodeWidget { node, mut widget, widget_id, pinned_widget_id, layer } = self;
         widget = map(widget);
-        NodeWidget { node, widget, widget_id }
+        NodeWidget { node, widget, widget_id, pinned_widget_id, layer }
+    }
+
+    /// Instantiate the node's widget with the given `widget::Id` rather than one drawn from the
+    /// graph's pool of `widget::Id`s.
+    ///
+    /// This allows other widgets to refer to the node's widget by an `Id` that remains the same
+    /// across updates. The `Id` should be produced by the user's own `widget::id::Generator` (e.g.
+    /// via `widget_ids!`) so that it is distinct from those within the graph's pool. If the `Id`
+    /// belongs to the graph's pool or has already been used for another node during this update,
+    /// it is ignored and an `Id` is drawn from the pool as usual.
+    pub fn with_widget_id(mut self, widget_id: widget::Id) -> Self {
+        self.pinned_widget_id = Some(widget_id);
+        self
+    }
+
+    /// Assign the node to the given layer.
+    ///
+    /// Nodes are instantiated in order of their layer, so nodes in higher layers are drawn above
+    /// those in lower layers. See `Graph::edge_layer` for drawing edges between layers. As layers
+    /// are recorded upon instantiation, a change in layer takes effect from the following update.
+    /// The default layer is `0`.
+    pub fn layer(mut self, layer: i8) -> Self {
+        self.layer = layer;
+        self
     }
 
     /// Set the given widget for the node at `node_id()`.
     pub fn set(self, ui: &mut UiCell) -> W::Event {
         let widget_id = self.widget_id(ui);
-        let NodeWidget { node, widget, .. } = self;
-        widget
-            .xy_relative_to(node.graph_id, node.point)
+        let NodeWidget { node, widget, layer, .. } = self;
+        let camera_point = {
+            let mut shared = node.shared.lock().unwrap();
+            shared.node_layers.insert(node.node_id, layer);
+            shared.camera.point
+        };
+        let xy = [node.point[0] - camera_point[0], node.point[1] - camera_point[1]];
+        let event = widget
+            .xy_relative_to(node.graph_id, xy)
             .parent(node.graph_id)
-            .set(widget_id, ui)
+            .set(widget_id, ui);
+
+        // Record whether or not the node is hovered for the edge instantiation stage.
+        let is_hovered = ui.global_input().current.widget_under_mouse
+            .map(|w| w == widget_id || is_descendant_of(w, widget_id, ui))
+            .unwrap_or(false);
+        if is_hovered {
+            node.shared.lock().unwrap().hovered_node = Some(node.node_id);
+        }
+
+        event
     }
 }
 
@@ -641,6 +2509,50 @@ where
     }
 }
 
+impl<'a, NI, A, B> AnyNodeWidget<'a, NI, A, B>
+where
+    NI: NodeId,
+    A: 'static + Widget,
+    B: 'static + Widget,
+{
+    /// Retrieve the `widget::Id` that will be used to instantiate this node's widget.
+    pub fn widget_id(&self, ui: &mut UiCell) -> widget::Id {
+        match *self {
+            AnyNodeWidget::A(ref node) => node.widget_id(ui),
+            AnyNodeWidget::B(ref node) => node.widget_id(ui),
+        }
+    }
+
+    /// Assign the node to the given layer. See `NodeWidget::layer`.
+    pub fn layer(self, layer: i8) -> Self {
+        match self {
+            AnyNodeWidget::A(node) => AnyNodeWidget::A(node.layer(layer)),
+            AnyNodeWidget::B(node) => AnyNodeWidget::B(node.layer(layer)),
+        }
+    }
+
+    /// Set the widget for the node at `node_id()`.
+    pub fn set(self, ui: &mut UiCell) -> AnyNodeEvent<A::Event, B::Event> {
+        match self {
+            AnyNodeWidget::A(node) => AnyNodeEvent::A(node.set(ui)),
+            AnyNodeWidget::B(node) => AnyNodeEvent::B(node.set(ui)),
+        }
+    }
+}
+
+impl<'a, NI, A, B> std::ops::Deref for AnyNodeWidget<'a, NI, A, B>
+where
+    NI: NodeId,
+{
+    type Target = NodeContext<'a, NI>;
+    fn deref(&self) -> &Self::Target {
+        match *self {
+            AnyNodeWidget::A(ref node) => &node.node,
+            AnyNodeWidget::B(ref node) => &node.node,
+        }
+    }
+}
+
 impl<'a, NI> Edge<'a, NI>
 where
     NI: NodeId,
@@ -664,126 +2576,1248 @@ where
         (self.start, self.end)
     }
 
-    /// Specify the widget to use 
-    pub fn widget<W>(self, widget: W) -> EdgeWidget<'a, NI, W> {
-        EdgeWidget {
-            edge: self,
-            widget,
-            widget_id: Cell::new(None),
-        }
+    /// The index of this edge among all edges that share its start and end nodes.
+    ///
+    /// Edges are indexed in the order in which they are yielded by the graph.
+    pub fn parallel_index(&self) -> usize {
+        self.parallel_index
     }
-}
 
-/// Returns the `widget::Id` for a node if one exists.
-///
-/// Returns `None` if there is no `Graph` for the given `graph_id` or if there is not yet a
-/// `widget::Id` for the given `node_id`.
-///
-/// This will always return `None` if called between calls to the `Graph::set` and node
-/// instantiation stages, as `widget::Id`s for nodes are only populated during the node
-/// instantiation stage.
-pub fn node_widget_id<NI>(node_id: NI, graph_id: widget::Id, ui: &Ui) -> Option<widget::Id>
-where
-    NI: NodeId,
-{
-    ui.widget_graph()
-        .widget(graph_id)
-        .and_then(|container| container.state_and_style::<State<NI>, Style>())
-        .and_then(|unique| {
-            let shared = unique.state.shared.lock().unwrap();
-            shared.widget_id_map.node_widget_ids.get(&node_id).map(|&id| id)
-        })
-}
+    /// The total number of edges that share this edge's start and end nodes, including this edge.
+    pub fn parallel_count(&self) -> usize {
+        self.parallel_count
+    }
 
-/// Returns the `widget::Id`s for the start and end nodes.
-///
-/// `Edge`s can only exist for the lifetime of a `SessionEdges`, thus we assume that there will
-/// always be a `Graph` for the edge's `graph_id` and that there will always be a `widget::Id` for
-/// the start and end nodes.
-///
-/// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
-/// widget.
-pub fn edge_node_widget_ids<NI>(edge: &Edge<NI>, ui: &Ui) -> (widget::Id, widget::Id)
-where
-    NI: NodeId,
-{
-    ui.widget_graph()
-        .widget(edge.graph_id)
-        .and_then(|container| container.state_and_style::<State<NI>, Style>())
-        .map(|unique| {
-            let shared = unique.state.shared.lock().unwrap();
-            let a = shared.widget_id_map.node_widget_ids.get(&edge.start.id).map(|&id| id);
-            let b = shared.widget_id_map.node_widget_ids.get(&edge.end.id).map(|&id| id);
-            (a.expect("no `widget::Id` for start node"), b.expect("no `widget::Id` for end node"))
-        })
-        .expect("no graph associated with edge's `graph_id` was found")
-}
+    /// The lateral offset for this edge that fans out all edges sharing its start and end nodes
+    /// with the given spacing between each.
+    ///
+    /// The offsets are centred on `0.0`, so a lone edge always has an offset of `0.0`.
+    pub fn parallel_offset(&self, spacing: Scalar) -> Scalar {
+        parallel_offset(self.parallel_index, self.parallel_count, spacing)
+    }
 
-impl<'a, NI, W> EdgeWidget<'a, NI, W>
-where
-    NI: NodeId,
-    W: 'static + Widget,
-{
-    /// Retrieve the `widget::Id` that will be used to instantiate this edge's widget.
-    pub fn widget_id(&self, ui: &mut UiCell) -> widget::Id {
-        match self.widget_id.get() {
-            Some(id) => id,
+    /// The position of the output socket at the start of the edge.
+    ///
+    /// This is the same position used by the built-in edge widgets, so custom edge widgets may use
+    /// it to meet the rendered socket exactly.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn start_socket_point(&self, ui: &Ui) -> Point {
+        self.socket_points(ui).0
+    }
+
+    /// The position of the input socket at the end of the edge.
+    ///
+    /// See `Edge::start_socket_point`.
+    pub fn end_socket_point(&self, ui: &Ui) -> Point {
+        self.socket_points(ui).1
+    }
+
+    /// The rectangles of the start and end nodes respectively, as returned by `node::node_rect`.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn node_rects(&self, ui: &Ui) -> (Rect, Rect) {
+        let (start_id, end_id) = edge_node_widget_ids(self, ui);
+        let start = node::node_rect(start_id, ui).expect("no node widget found for `start_id`");
+        let end = node::node_rect(end_id, ui).expect("no node widget found for `end_id`");
+        (start, end)
+    }
+
+    // The position of the start and end sockets.
+    //
+    // Falls back to the position of each node within the `Layout` if the node widgets have not
+    // yet been instantiated during this update, e.g. within **SessionEdgesFirst**.
+    fn socket_points(&self, ui: &Ui) -> (Point, Point) {
+        match try_edge_node_widget_ids(self, ui) {
+            Some(_) => node::edge_socket_points(self, ui),
+            None => (self.layout_point(self.start.id, ui), self.layout_point(self.end.id, ui)),
+        }
+    }
+
+    // The sides of the nodes on which the start and end sockets lie.
+    //
+    // Falls back to the graph's socket layouts if the node widgets have not yet been instantiated.
+    //
+    // For undirected graphs, each side is flipped as necessary to face the other socket so that
+    // the edge is routed the same way regardless of its direction.
+    fn socket_sides(&self, ui: &Ui) -> (SocketSide, SocketSide) {
+        let (start_side, end_side) = match try_edge_node_widget_ids(self, ui) {
+            Some(_) => node::edge_socket_sides(self, ui),
             None => {
-                // Request a `widget::Id` from the `WidgetIdMap`.
-                let mut shared = self.edge.shared.lock().unwrap();
-                let id = shared.widget_id_map.next_id_for_edge::<W>(&mut ui.widget_id_generator());
-                self.widget_id.set(Some(id));
-                id
+                let shared = self.shared.lock().unwrap();
+                let SocketLayouts { input, output } = shared.socket_layouts;
+                (output.side, input.side)
             },
+        };
+        if self.shared.lock().unwrap().directed {
+            return (start_side, end_side);
         }
+        let (start, end) = self.socket_points(ui);
+        (side_facing(start_side, start, end), side_facing(end_side, end, start))
     }
 
-    /// Apply the given function to the inner widget.
-    pub fn map<M>(self, map: M) -> Self
-    where
-        M: FnOnce(W) -> W,
-    {
-        let EdgeWidget { edge, mut widget, widget_id } = self;
-        widget = map(widget);
-        EdgeWidget { edge, widget, widget_id }
+    // The rectangle of the start node's widget.
+    //
+    // Falls back to the node's position within the `Layout` and the dimensions of its widget as of
+    // its last instantiation if the node widget has not yet been instantiated.
+    fn start_node_rect(&self, ui: &Ui) -> Rect {
+        match try_edge_node_widget_ids(self, ui) {
+            Some((start_id, _)) => ui.rect_of(start_id)
+                .expect("no node widget found for the edge's `start_id`"),
+            None => {
+                let xy = self.layout_point(self.start.id, ui);
+                let shared = self.shared.lock().unwrap();
+                let dim = shared.node_dims.get(&self.start.id).cloned().unwrap_or([0.0; 2]);
+                Rect::from_xy_dim(xy, dim)
+            },
+        }
     }
 
-    /// Set the given widget for the edge.
-    pub fn set(self, ui: &mut UiCell) -> W::Event {
-        let widget_id = self.widget_id(ui);
-        let EdgeWidget { edge, widget, .. } = self;
-        widget
-            .parent(edge.graph_id)
-            .set(widget_id, ui)
+    // The absolute position of the given node within the `Layout`, as viewed through the camera.
+    fn layout_point(&self, node_id: NI, ui: &Ui) -> Point {
+        let graph_xy = ui.xy_of(self.graph_id)
+            .expect("no graph associated with edge's `graph_id` was found");
+        let shared = self.shared.lock().unwrap();
+        let point = shared.nodes.get(&node_id).map(|node| node.point).unwrap_or([0.0; 2]);
+        let camera = shared.camera.point;
+        [graph_xy[0] + point[0] - camera[0], graph_xy[1] + point[1] - camera[1]]
     }
-}
 
-impl<'a, N, E> Graph<'a, N, E>
-where
-    N: Iterator,
-    N::Item: NodeId,
-    E: Iterator<Item=(NodeSocket<N::Item>, NodeSocket<N::Item>)>,
-{
-    /// Begin building a new **Graph** widget.
-    pub fn new<NI, EI>(nodes: NI, edges: EI, layout: &'a Layout<NI::Item>) -> Self
-    where
-        NI: IntoIterator<IntoIter=N, Item=N::Item>,
-        EI: IntoIterator<IntoIter=E, Item=(NodeSocket<N::Item>, NodeSocket<N::Item>)>,
-    {
-        Graph {
-            common: widget::CommonBuilder::default(),
-            style: Style::default(),
-            nodes: nodes.into_iter(),
-            edges: edges.into_iter(),
-            layout: layout,
+    /// Draw the given text at the midpoint between the edge's start and end sockets, e.g. to
+    /// display a weight carried by the edge.
+    ///
+    /// The text remains upright regardless of the slope of the edge and is drawn over a rounded
+    /// background according to the graph's `EdgeLabelStyle`. Labels are drawn above the graph's
+    /// nodes and edges so that they remain legible. This may be called before the edge's own
+    /// widget is set.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn label(&self, ui: &mut UiCell, text: &str) {
+        let (start, end) = self.socket_points(ui);
+        let midpoint = [(start[0] + end[0]) / 2.0, (start[1] + end[1]) / 2.0];
+        let (style, text_id, background_id) = {
+            let mut shared = self.shared.lock().unwrap();
+            let id_gen = &mut ui.widget_id_generator();
+            let text_id = shared.widget_id_map.next_id_for_edge::<widget::Text>(id_gen);
+            let background_id = shared.widget_id_map
+                .next_id_for_edge::<widget::RoundedRectangle>(id_gen);
+            (shared.edge_label_style, text_id, background_id)
+        };
+        widget::Text::new(text)
+            .font_size(style.font_size)
+            .color(style.color)
+            .xy(midpoint)
+            .depth(EDGE_LABEL_DEPTH - 1.0)
+            .parent(self.graph_id)
+            .graphics_for(self.graph_id)
+            .set(text_id, ui);
+        if let (Some(color), Some(text_rect)) = (style.background_color, ui.rect_of(text_id)) {
+            let background_rect = text_rect.pad(-style.padding);
+            let radius = background_rect.h() / 2.0;
+            widget::RoundedRectangle::fill(background_rect.dim(), radius)
+                .xy(background_rect.xy())
+                .color(color)
+                .depth(EDGE_LABEL_DEPTH)
+                .parent(self.graph_id)
+                .graphics_for(self.graph_id)
+                .set(background_id, ui);
         }
     }
 
+    // The color used for the widgets produced by the `Edge` methods.
+    fn color(&self) -> Color {
+        let shared = self.shared.lock().unwrap();
+        shared.edge_style.color
+            .or(shared.colors.map(|c| c.edge))
+            .unwrap_or(color::DARK_CHARCOAL)
+    }
+
+    /// Use a straight line from the start socket to the end socket as the widget for this edge.
+    ///
+    /// The line is styled using the graph's `EdgeStyle`, which may be overridden via the
+    /// returned `EdgeWidget`'s `thickness` and `color` methods. Edges sharing the same start and
+    /// end nodes are offset perpendicular to the line by the `PARALLEL_EDGE_SPACING` so that they
+    /// remain distinguishable.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn straight_line(self, ui: &Ui) -> EdgeWidget<'a, NI, widget::Line> {
+        let (start, end) = self.socket_points(ui);
+        let offset = self.parallel_offset(PARALLEL_EDGE_SPACING);
+        let (start, end) = offset_segment(start, end, offset);
+        let edge_color = self.color();
+        let edge_style = self.shared.lock().unwrap().edge_style;
+        let mut line = widget::Line::abs(start, end)
+            .thickness(edge_style.thickness)
+            .color(edge_color);
+        line.style.cap = edge_style.cap;
+        self.widget(line)
+    }
+
+    /// Whether or not this edge starts or ends at the node currently under the mouse.
+    ///
+    /// This may be used to highlight the edges connected to a hovered node.
+    pub fn is_incident_to_hovered(&self) -> bool {
+        match self.shared.lock().unwrap().hovered_node {
+            Some(node_id) => self.start.id == node_id || self.end.id == node_id,
+            None => false,
+        }
+    }
+
+    /// Whether or not the edge starts and ends at the same node.
+    pub fn is_self_loop(&self) -> bool {
+        self.start.id == self.end.id
+    }
+
+    /// Specify the widget to use 
+    pub fn widget<W>(self, widget: W) -> EdgeWidget<'a, NI, W> {
+        EdgeWidget {
+            edge: self,
+            widget,
+            widget_id: Cell::new(None),
+        }
+    }
+
+    /// Use a thick polyline through the given absolute `points` as the widget for this edge.
+    ///
+    /// Unlike `widget::PointPath`, each corner is filled according to the given `join` so that
+    /// multi-segment paths (e.g. orthogonally routed edges) render without gaps at their corners.
+    ///
+    /// The resulting `widget::Triangles` may be colored via `map` before calling `set`.
+    pub fn thick_path<I>(self, points: I, thickness: Scalar, join: Join)
+        -> EdgeWidget<'a, NI, EdgeTriangles>
+    where
+        I: IntoIterator<Item=Point>,
+    {
+        let triangles = path::triangles(points, thickness, join);
+        let edge_color = self.color();
+        self.widget(edge_triangles(triangles, edge_color))
+    }
+
+    /// Use a dashed, thick polyline through the given absolute `points` as the widget for this
+    /// edge.
+    ///
+    /// The path is split into dashes of `dash_length` separated by gaps of `gap_length`, with
+    /// the pattern continuing across corners. Corners within each dash are filled using
+    /// `Join::Miter`. This may be used to dash straight, curved (e.g. by sampling a Bézier via
+    /// `path::cubic_bezier`) and orthogonally routed edges alike.
+    ///
+    /// The resulting `widget::Triangles` may be colored via `map` before calling `set`.
+    pub fn dashed_path<I>(
+        self,
+        points: I,
+        thickness: Scalar,
+        dash_length: Scalar,
+        gap_length: Scalar,
+    ) -> EdgeWidget<'a, NI, EdgeTriangles>
+    where
+        I: IntoIterator<Item=Point>,
+    {
+        let triangles = path::dashes(points, dash_length, gap_length)
+            .into_iter()
+            .flat_map(|dash| path::triangles(dash, thickness, Join::Miter))
+            .collect();
+        let edge_color = self.color();
+        self.widget(edge_triangles(triangles, edge_color))
+    }
+
+    /// Use a dashed straight line from the start socket to the end socket as the widget for this
+    /// edge.
+    ///
+    /// See `dashed_path` for details.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn dashed_line(self, ui: &Ui, thickness: Scalar, dash_length: Scalar, gap_length: Scalar)
+        -> EdgeWidget<'a, NI, EdgeTriangles>
+    {
+        let (start, end) = self.socket_points(ui);
+        self.dashed_path(vec![start, end], thickness, dash_length, gap_length)
+    }
+
+    /// Use a straight line from the start socket to the end socket with an arrowhead at the end
+    /// socket as the widget for this edge.
+    ///
+    /// `arrow_size` describes both the length and width of the arrowhead. The line and arrowhead
+    /// are described by a single `widget::Triangles`, so only one `widget::Id` is required per
+    /// edge. The triangles may be colored via `map` before calling `set`. The arrowhead is omitted
+    /// if the graph is undirected (see `Graph::directed`).
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn straight_line_with_arrow(self, ui: &Ui, thickness: Scalar, arrow_size: Scalar)
+        -> EdgeWidget<'a, NI, EdgeTriangles>
+    {
+        let (start, end) = self.socket_points(ui);
+        let directed = self.shared.lock().unwrap().directed;
+        let triangles = match directed {
+            true => path::arrow_triangles(start, end, thickness, arrow_size),
+            false => path::triangles(vec![start, end], thickness, Join::Miter),
+        };
+        let edge_color = self.color();
+        self.widget(edge_triangles(triangles, edge_color))
+    }
+
+    /// Use a straight line from the start socket to the end socket whose color fades from
+    /// `start_color` to `end_color` as the widget for this edge.
+    ///
+    /// This emphasises the direction of the edge without an arrowhead. The line has the thickness
+    /// of the graph's `EdgeStyle` and is centred on the segment between the sockets. The colors of
+    /// the sockets themselves are available via `Edge::socket_colors`.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn gradient_line(self, ui: &Ui, start_color: Color, end_color: Color)
+        -> EdgeWidget<'a, NI, EdgeGradientTriangles>
+    {
+        let (start, end) = self.socket_points(ui);
+        let thickness = self.shared.lock().unwrap().edge_style.thickness;
+        let triangles = path::gradient_triangles(start, end, thickness, start_color.into(),
+                                                 end_color.into());
+        let bounding_rect = Rect::from_corners(start, end).pad(-thickness / 2.0);
+        let widget = widget::Triangles::multi_color(triangles).with_bounding_rect(bounding_rect);
+        self.widget(widget)
+    }
+
+    /// The colors of the output socket at the start of the edge and the input socket at the end
+    /// of the edge respectively, as returned by `node::socket_color`.
+    ///
+    /// Returns `None` if either node widget has not yet been instantiated during this update or is
+    /// not a `node::Node`.
+    pub fn socket_colors(&self, ui: &Ui) -> Option<(Color, Color)> {
+        let (start_id, end_id) = match try_edge_node_widget_ids(self, ui) {
+            Some(ids) => ids,
+            None => return None,
+        };
+        let start = node::socket_color(start_id, SocketType::Output, self.start.socket_index, ui);
+        let end = node::socket_color(end_id, SocketType::Input, self.end.socket_index, ui);
+        match (start, end) {
+            (Some(start), Some(end)) => Some((start, end)),
+            _ => None,
+        }
+    }
+
+    /// Use a cubic Bézier curve from the start socket to the end socket as the widget for this
+    /// edge.
+    ///
+    /// The curve leaves and enters each socket perpendicular to the side of the node on which the
+    /// socket lies, e.g. a `SocketSide::Right` output leaves rightward while a `SocketSide::Left`
+    /// input is entered from the left. The control points are offset by half the distance
+    /// between the sockets. Edges sharing the same start and end nodes are fanned out by the
+    /// `PARALLEL_EDGE_SPACING` so that they remain distinguishable. The curve is sampled into
+    /// `segments` straight lines, falling back to a single straight segment when the sockets
+    /// coincide.
+    ///
+    /// Self-loops are rendered via `self_loop`.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn bezier(self, ui: &Ui, segments: usize)
+        -> EdgeWidget<'a, NI, widget::PointPath<Vec<Point>>>
+    {
+        if self.is_self_loop() {
+            return self.self_loop(ui, segments);
+        }
+        let (start, end) = self.socket_points(ui);
+        let (start_side, end_side) = self.socket_sides(ui);
+        let distance = ((end[0] - start[0]).powi(2) + (end[1] - start[1]).powi(2)).sqrt();
+        let points = if distance < std::f64::EPSILON {
+            vec![start, end]
+        } else {
+            let offset = distance / 2.0;
+            // Fan out parallel edges perpendicular to the line between the sockets.
+            let lateral = self.parallel_offset(PARALLEL_EDGE_SPACING);
+            let normal = [-(end[1] - start[1]) / distance, (end[0] - start[0]) / distance];
+            let ctrl = |p: Point, side: SocketSide| {
+                let [x, y] = side_direction(side);
+                [p[0] + x * offset + normal[0] * lateral, p[1] + y * offset + normal[1] * lateral]
+            };
+            path::cubic_bezier(start, ctrl(start, start_side), ctrl(end, end_side), end, segments)
+        };
+        let edge_color = self.color();
+        self.widget(widget::PointPath::abs(points).color(edge_color))
+    }
+
+    /// Use a loop from the start socket around the outside of the node and back into the end
+    /// socket as the widget for this edge.
+    ///
+    /// This is intended for edges that start and end at the same node. The loop leaves and
+    /// enters the sockets perpendicular to their sides and bulges past the node's rectangle in
+    /// the direction perpendicular to the start socket's side (above the node for sockets on the
+    /// left or right, to the right of the node for sockets on the top or bottom). The size of the
+    /// loop is proportional to the size of the node. The loop is sampled into `segments` straight
+    /// lines.
+    ///
+    /// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+    /// widget.
+    pub fn self_loop(self, ui: &Ui, segments: usize)
+        -> EdgeWidget<'a, NI, widget::PointPath<Vec<Point>>>
+    {
+        let (start, end) = self.socket_points(ui);
+        let (start_side, end_side) = self.socket_sides(ui);
+        let node_rect = self.start_node_rect(ui);
+        let size = node_rect.w().max(node_rect.h());
+        let bulge = match start_side {
+            SocketSide::Left | SocketSide::Right => [0.0, 1.0],
+            SocketSide::Top | SocketSide::Bottom => [1.0, 0.0],
+        };
+        let ctrl = |p: Point, side: SocketSide| {
+            let [x, y] = side_direction(side);
+            [p[0] + (x + bulge[0]) * size, p[1] + (y + bulge[1]) * size]
+        };
+        let points = path::cubic_bezier(start, ctrl(start, start_side), ctrl(end, end_side), end,
+                                        segments);
+        let edge_color = self.color();
+        self.widget(widget::PointPath::abs(points).color(edge_color))
+    }
+}
+
+// The distance moved by the given drag, or nothing until the total distance dragged since the
+// button was pressed exceeds the `threshold`.
+//
+// The whole distance dragged so far is applied at once when the threshold is first exceeded, so
+// that the dragged item catches up with the mouse.
+fn thresholded_delta(drag: &event::Drag, threshold: Scalar, engaged: &mut bool) -> [Scalar; 2] {
+    if *engaged {
+        return drag.delta_xy;
+    }
+    let [x, y] = drag.total_delta_xy;
+    if (x * x + y * y).sqrt() <= threshold {
+        return [0.0; 2];
+    }
+    *engaged = true;
+    drag.total_delta_xy
+}
+
+// Whether or not the left drag described by `drag_engaged` began over the given widget and has
+// exceeded the drag threshold.
+fn is_drag_engaged(drag_engaged: Option<(widget::Id, bool)>, widget_id: widget::Id) -> bool {
+    match drag_engaged {
+        Some((id, engaged)) => id == widget_id && engaged,
+        None => false,
+    }
+}
+
+// Whether or not the given edge is equal to any of the given `edges`, as described by the given
+// `directionality`. Always `false` if no `directionality` is given.
+fn is_duplicate<NI>(edges: &[(NodeSocket<NI>, NodeSocket<NI>)],
+                    edge: (NodeSocket<NI>, NodeSocket<NI>),
+                    directionality: Option<Directionality>) -> bool
+where
+    NI: NodeId,
+{
+    directionality
+        .map(|d| edges.iter().any(|&other| d.is_same_edge(other, edge)))
+        .unwrap_or(false)
+}
+
+// The side on the same axis as the given `side` that faces from `point` toward `target`.
+fn side_facing(side: SocketSide, point: Point, target: Point) -> SocketSide {
+    match side {
+        SocketSide::Left | SocketSide::Right => match target[0] < point[0] {
+            true => SocketSide::Left,
+            false => SocketSide::Right,
+        },
+        SocketSide::Top | SocketSide::Bottom => match target[1] < point[1] {
+            true => SocketSide::Bottom,
+            false => SocketSide::Top,
+        },
+    }
+}
+
+// The unit vector pointing outward from the given side of a node.
+fn side_direction(side: SocketSide) -> [Scalar; 2] {
+    match side {
+        SocketSide::Left => [-1.0, 0.0],
+        SocketSide::Right => [1.0, 0.0],
+        SocketSide::Top => [0.0, 1.0],
+        SocketSide::Bottom => [0.0, -1.0],
+    }
+}
+
+/// The widget produced by the `Edge` methods that describe their geometry as triangles.
+pub type EdgeTriangles = widget::Triangles<SingleColor, Vec<Triangle<Point>>>;
+
+/// The widget produced by `Edge::gradient_line`, whose triangles are colored per vertex.
+pub type EdgeGradientTriangles = widget::Triangles<MultiColor, Vec<Triangle<ColoredPoint>>>;
+
+// Produce a `widget::Triangles` of the given color bounded by the given triangles.
+fn edge_triangles(triangles: Vec<Triangle<Point>>, color: Color) -> EdgeTriangles {
+    let bounding_rect = path::bounding_rect(&triangles)
+        .unwrap_or_else(|| Rect::from_xy_dim([0.0; 2], [0.0; 2]));
+    widget::Triangles::single_color(color, triangles)
+        .with_bounding_rect(bounding_rect)
+}
+
+// The triangles for a grid of lines with the given spacing across the given graph `rect`, as
+// viewed through a camera at `camera_point`.
+//
+// Lines are placed at multiples of the spacing within the space of the `Layout`, so that the grid
+// stays fixed relative to the nodes as the camera moves.
+fn grid_triangles(rect: Rect, spacing: Scalar, camera_point: Point) -> Vec<Triangle<Point>> {
+    let half = GRID_LINE_THICKNESS / 2.0;
+    let mut triangles = vec![];
+    for x in grid_line_offsets(rect.w() / 2.0, spacing, camera_point[0]) {
+        let line = Rect::from_corners([rect.x() + x - half, rect.bottom()],
+                                      [rect.x() + x + half, rect.top()]);
+        let (a, b) = widget::primitive::shape::rectangle::triangles(line);
+        triangles.push(a);
+        triangles.push(b);
+    }
+    for y in grid_line_offsets(rect.h() / 2.0, spacing, camera_point[1]) {
+        let line = Rect::from_corners([rect.left(), rect.y() + y - half],
+                                      [rect.right(), rect.y() + y + half]);
+        let (a, b) = widget::primitive::shape::rectangle::triangles(line);
+        triangles.push(a);
+        triangles.push(b);
+    }
+    triangles
+}
+
+// The offsets from the centre of the graph along a single axis of each grid line within the
+// given half extent of the view, for a camera at the given position along the same axis.
+fn grid_line_offsets(half_extent: Scalar, spacing: Scalar, camera: Scalar) -> Vec<Scalar> {
+    let mut offsets = vec![];
+    let mut offset = ((camera - half_extent) / spacing).ceil() * spacing - camera;
+    while offset <= half_extent {
+        offsets.push(offset);
+        offset += spacing;
+    }
+    offsets
+}
+
+// The lateral offset of the edge at `index` among `count` edges sharing the same start and end
+// nodes, centred on `0.0` with the given spacing between each.
+fn parallel_offset(index: usize, count: usize, spacing: Scalar) -> Scalar {
+    (index as Scalar - (count - 1) as Scalar / 2.0) * spacing
+}
+
+// The segment between `start` and `end` moved perpendicular to itself by the given offset.
+//
+// Positive offsets move the segment to its left, as in `Edge::bezier`. Segments of zero length are
+// returned unchanged.
+fn offset_segment(start: Point, end: Point, offset: Scalar) -> (Point, Point) {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance < std::f64::EPSILON || offset == 0.0 {
+        return (start, end);
+    }
+    let normal = [-dy / distance * offset, dx / distance * offset];
+    let shift = |p: Point| [p[0] + normal[0], p[1] + normal[1]];
+    (shift(start), shift(end))
+}
+
+// The distance from the point `p` to the line segment between `a` and `b`.
+fn distance_to_segment(p: Point, a: Point, b: Point) -> Scalar {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let ap = [p[0] - a[0], p[1] - a[1]];
+    let len_sq = ab[0] * ab[0] + ab[1] * ab[1];
+    let t = if len_sq > 0.0 {
+        ((ap[0] * ab[0] + ap[1] * ab[1]) / len_sq).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+    let closest = [a[0] + ab[0] * t, a[1] + ab[1] * t];
+    ((p[0] - closest[0]).powi(2) + (p[1] - closest[1]).powi(2)).sqrt()
+}
+
+// Whether or not `widget_id` is a descendant of `ancestor` within the widget graph.
+fn is_descendant_of(widget_id: widget::Id, ancestor: widget::Id, ui: &Ui) -> bool {
+    let mut parent = ui.widget_graph().depth_parent(widget_id);
+    while let Some(id) = parent {
+        if id == ancestor {
+            return true;
+        }
+        parent = ui.widget_graph().depth_parent(id);
+    }
+    false
+}
+
+// Whether or not the given widget is a descendant of one of the given node widgets.
+//
+// Returns `false` for the node widgets themselves.
+fn is_within_node_widget<NI>(
+    widget_id: widget::Id,
+    node_widget_ids: &HashMap<NI, widget::Id>,
+    ui: &Ui,
+) -> bool
+where
+    NI: NodeId,
+{
+    let mut parent = ui.widget_graph().depth_parent(widget_id);
+    while let Some(id) = parent {
+        if node_widget_ids.values().any(|&node_id| node_id == id) {
+            return true;
+        }
+        parent = ui.widget_graph().depth_parent(id);
+    }
+    false
+}
+
+// The events produced by connecting a new edge from `start` to `end`, given the edges already
+// `connected` to the input socket and the `Graph::input_capacity`.
+//
+// Returns the `EdgeEvent::Remove`s for any edges replaced, followed by either the
+// `EdgeEvent::Add` or the `EdgeEvent::Cancelled` for the `origin` socket. An input socket with a
+// capacity of `0` never accepts an edge, regardless of the overflow mode.
+fn connect_input<NI>(
+    start: NodeSocket<NI>,
+    end: NodeSocket<NI>,
+    origin: NodeSocket<NI>,
+    connected: &[(NodeSocket<NI>, NodeSocket<NI>)],
+    input_capacity: Option<(usize, InputOverflow)>,
+) -> (Vec<EdgeEvent<NI>>, EdgeEvent<NI>)
+where
+    NI: NodeId,
+{
+    let (capacity, overflow) = match input_capacity {
+        None => return (vec![], EdgeEvent::Add { start, end }),
+        Some((0, _)) => return (vec![], EdgeEvent::Cancelled(origin)),
+        Some(input_capacity) => input_capacity,
+    };
+    let excess = (connected.len() + 1).saturating_sub(capacity);
+    match overflow {
+        _ if excess == 0 => (vec![], EdgeEvent::Add { start, end }),
+        InputOverflow::Reject => (vec![], EdgeEvent::Cancelled(origin)),
+        InputOverflow::Replace => {
+            let removed = connected.iter()
+                .take(excess)
+                .map(|&(start, end)| EdgeEvent::Remove { start, end })
+                .collect();
+            (removed, EdgeEvent::Add { start, end })
+        },
+    }
+}
+
+// Update the `edges` and their `edge_keys` from the previous update with the given keyed edges.
+//
+// Edges are kept at their index up to the first changed key, with their sockets replaced by the
+// new ones. All edges from the first changed key onward are recreated.
+fn update_keyed_edges<NI>(
+    edges: &mut Vec<(NodeSocket<NI>, NodeSocket<NI>)>,
+    edge_keys: &mut Vec<u64>,
+    keyed_edges: Vec<(u64, (NodeSocket<NI>, NodeSocket<NI>))>,
+)
+where
+    NI: NodeId,
+{
+    let unchanged = edge_keys.iter()
+        .zip(&keyed_edges)
+        .take_while(|&(&prev_key, &(key, _))| prev_key == key)
+        .count();
+    edges.truncate(unchanged);
+    edge_keys.truncate(unchanged);
+    for (i, (key, edge)) in keyed_edges.into_iter().enumerate() {
+        match i < unchanged {
+            true => edges[i] = edge,
+            false => {
+                edges.push(edge);
+                edge_keys.push(key);
+            },
+        }
+    }
+}
+
+/// Returns the `widget::Id` for a node if one exists.
+///
+/// Returns `None` if there is no `Graph` for the given `graph_id` or if there is not yet a
+/// `widget::Id` for the given `node_id`.
+///
+/// This will always return `None` if called between calls to the `Graph::set` and node
+/// instantiation stages, as `widget::Id`s for nodes are only populated during the node
+/// instantiation stage.
+pub fn node_widget_id<NI>(node_id: NI, graph_id: widget::Id, ui: &Ui) -> Option<widget::Id>
+where
+    NI: NodeId,
+{
+    ui.widget_graph()
+        .widget(graph_id)
+        .and_then(|container| container.state_and_style::<State<NI>, Style>())
+        .and_then(|unique| {
+            let shared = unique.state.shared.lock().unwrap();
+            shared.widget_id_map.node_widget_ids.get(&node_id).map(|&id| id)
+        })
+}
+
+/// The rectangle of the widget for the node with the given identifier within the graph of the
+/// given `graph_id`, as returned by `node::node_rect`.
+///
+/// Returns `None` if the node has no widget or if its widget is not a `node::Node`.
+pub fn node_rect<NI>(node_id: NI, graph_id: widget::Id, ui: &Ui) -> Option<Rect>
+where
+    NI: NodeId,
+{
+    node_widget_id(node_id, graph_id, ui).and_then(|widget_id| node::node_rect(widget_id, ui))
+}
+
+/// Returns the `widget::Id`s for the start and end nodes.
+///
+/// `Edge`s yielded by a `SessionEdges` can only exist after the nodes have been instantiated,
+/// thus we assume that there will always be a `Graph` for the edge's `graph_id` and that there
+/// will always be a `widget::Id` for the start and end nodes. This is not the case for `Edge`s
+/// yielded by a `SessionEdgesFirst`.
+///
+/// **Panic!**s if the given `Ui` is not the same one used to create the edge's parent Graph
+/// widget.
+pub fn edge_node_widget_ids<NI>(edge: &Edge<NI>, ui: &Ui) -> (widget::Id, widget::Id)
+where
+    NI: NodeId,
+{
+    ui.widget_graph()
+        .widget(edge.graph_id)
+        .and_then(|container| container.state_and_style::<State<NI>, Style>())
+        .map(|unique| {
+            let shared = unique.state.shared.lock().unwrap();
+            let a = shared.widget_id_map.node_widget_ids.get(&edge.start.id).map(|&id| id);
+            let b = shared.widget_id_map.node_widget_ids.get(&edge.end.id).map(|&id| id);
+            (a.expect("no `widget::Id` for start node"), b.expect("no `widget::Id` for end node"))
+        })
+        .expect("no graph associated with edge's `graph_id` was found")
+}
+
+// The `widget::Id`s for the start and end nodes, or `None` if either node has not yet been
+// instantiated during this update.
+fn try_edge_node_widget_ids<NI>(edge: &Edge<NI>, ui: &Ui) -> Option<(widget::Id, widget::Id)>
+where
+    NI: NodeId,
+{
+    ui.widget_graph()
+        .widget(edge.graph_id)
+        .and_then(|container| container.state_and_style::<State<NI>, Style>())
+        .and_then(|unique| {
+            let shared = unique.state.shared.lock().unwrap();
+            let a = shared.widget_id_map.node_widget_ids.get(&edge.start.id).map(|&id| id);
+            let b = shared.widget_id_map.node_widget_ids.get(&edge.end.id).map(|&id| id);
+            match (a, b) {
+                (Some(a), Some(b)) => Some((a, b)),
+                _ => None,
+            }
+        })
+}
+
+impl<'a, NI, W> EdgeWidget<'a, NI, W>
+where
+    NI: NodeId,
+    W: 'static + Widget,
+{
+    /// Retrieve the `widget::Id` that will be used to instantiate this edge's widget.
+    pub fn widget_id(&self, ui: &mut UiCell) -> widget::Id {
+        match self.widget_id.get() {
+            Some(id) => id,
+            None => {
+                // Request a `widget::Id` from the `WidgetIdMap`.
+                let mut shared = self.edge.shared.lock().unwrap();
+                let id = shared.widget_id_map.next_id_for_edge::<W>(&mut ui.widget_id_generator());
+                self.widget_id.set(Some(id));
+                id
+            },
+        }
+    }
+
+    /// Apply the given function to the inner widget.
+    pub fn map<M>(self, map: M) -> Self
+    where
+        M: FnOnce(W) -> W,
+    {
+        let EdgeWidget { edge, mut widget, widget_id } = self;
+        widget = map(widget);
+        EdgeWidget { edge, widget, widget_id }
+    }
+
+    /// Set the color of the inner widget.
+    pub fn color(self, color: Color) -> Self
+    where
+        W: Colorable,
+    {
+        self.map(|widget| widget.color(color))
+    }
+
+    /// Set the given widget for the edge.
+    pub fn set(self, ui: &mut UiCell) -> W::Event {
+        let widget_id = self.widget_id(ui);
+        let EdgeWidget { edge, widget, .. } = self;
+        widget
+            .parent(edge.graph_id)
+            .set(widget_id, ui)
+    }
+}
+
+/// The iterator yielding the nodes of a **Graph** built via `Graph::from_data`.
+pub type DataNodes<'a, NI> = std::iter::Cloned<std::slice::Iter<'a, NI>>;
+/// The iterator yielding the edges of a **Graph** built via `Graph::from_data`.
+pub type DataEdges<'a, NI> =
+    std::iter::Cloned<std::slice::Iter<'a, (NodeSocket<NI>, NodeSocket<NI>)>>;
+
+impl<'a, NI> Graph<'a, DataNodes<'a, NI>, DataEdges<'a, NI>>
+where
+    NI: NodeId,
+{
+    /// Begin building a new **Graph** widget from the given explicitly mutated `GraphData`.
+    ///
+    /// Rather than comparing the nodes and edges against those of the previous update (which
+    /// costs time proportional to the number of nodes and edges), the **Graph** only does so when
+    /// the `GraphData`'s revision has changed since the last update. This suits large graphs that
+    /// rarely change.
+    pub fn from_data(data: &'a GraphData<NI>, layout: &'a Layout<NI>) -> Self {
+        let mut graph = Graph::new(data.nodes().iter().cloned(), data.edges().iter().cloned(),
+                                   layout);
+        graph.data_key = Some(data.key());
+        graph
+    }
+
+    /// Begin building a new **Graph** widget from the given `GraphData` that mutably borrows its
+    /// **Layout**.
+    ///
+    /// See `Graph::from_data` and `Graph::new_mut`.
+    pub fn from_data_mut(data: &'a GraphData<NI>, layout: &'a mut Layout<NI>) -> Self {
+        let mut graph = Graph::new_mut(data.nodes().iter().cloned(),
+                                       data.edges().iter().cloned(), layout);
+        graph.data_key = Some(data.key());
+        graph
+    }
+}
+
+impl<'a, N, E> Graph<'a, N, E>
+where
+    N: Iterator,
+    N::Item: NodeId,
+    E: Iterator<Item=(NodeSocket<N::Item>, NodeSocket<N::Item>)>,
+{
+    /// Begin building a new **Graph** widget.
+    pub fn new<NI, EI>(nodes: NI, edges: EI, layout: &'a Layout<NI::Item>) -> Self
+    where
+        NI: IntoIterator<IntoIter=N, Item=N::Item>,
+        EI: IntoIterator<IntoIter=E, Item=(NodeSocket<N::Item>, NodeSocket<N::Item>)>,
+    {
+        Graph {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            nodes: nodes.into_iter(),
+            edges: edges.into_iter(),
+            layout: LayoutRef::Ref(layout),
+            debug_label: None,
+            prune_layout: false,
+            connect_mode: ConnectMode::PressDrag,
+            max_nodes_per_frame: None,
+            colors: None,
+            edge_style: EdgeStyle::default(),
+            edge_label_style: EdgeLabelStyle::default(),
+            node_style: node::Style::default(),
+            grid_snap: None,
+            cull_offscreen: false,
+            edge_layer: None,
+            axis_lock_modifier: None,
+            input_capacity: None,
+            duplicate_edges: None,
+            directed: true,
+            connection_validator: None,
+            edge_key: None,
+            background: None,
+            socket_tooltip_delay: None,
+            camera: None,
+            pan_step: DEFAULT_PAN_STEP,
+            socket_snap_radius: DEFAULT_SOCKET_SNAP_RADIUS,
+            min_zoom: DEFAULT_MIN_ZOOM,
+            max_zoom: DEFAULT_MAX_ZOOM,
+            data_key: None,
+            widget_id_pool_limit: None,
+        }
+    }
+
+    /// Begin building a new **Graph** widget that mutably borrows its **Layout**.
+    ///
+    /// Whenever a node is dragged, its new position is written directly to the `layout`. A
+    /// `NodeEvent::Dragged` is still emitted so that the drag may be observed, but it need not be
+    /// handled in order to update the layout.
+    pub fn new_mut<NI, EI>(nodes: NI, edges: EI, layout: &'a mut Layout<NI::Item>) -> Self
+    where
+        NI: IntoIterator<IntoIter=N, Item=N::Item>,
+        EI: IntoIterator<IntoIter=E, Item=(NodeSocket<N::Item>, NodeSocket<N::Item>)>,
+    {
+        Graph {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            nodes: nodes.into_iter(),
+            edges: edges.into_iter(),
+            layout: LayoutRef::Mut(layout),
+            debug_label: None,
+            prune_layout: false,
+            connect_mode: ConnectMode::PressDrag,
+            max_nodes_per_frame: None,
+            colors: None,
+            edge_style: EdgeStyle::default(),
+            edge_label_style: EdgeLabelStyle::default(),
+            node_style: node::Style::default(),
+            grid_snap: None,
+            cull_offscreen: false,
+            edge_layer: None,
+            axis_lock_modifier: None,
+            input_capacity: None,
+            duplicate_edges: None,
+            directed: true,
+            connection_validator: None,
+            edge_key: None,
+            background: None,
+            socket_tooltip_delay: None,
+            camera: None,
+            pan_step: DEFAULT_PAN_STEP,
+            socket_snap_radius: DEFAULT_SOCKET_SNAP_RADIUS,
+            min_zoom: DEFAULT_MIN_ZOOM,
+            max_zoom: DEFAULT_MAX_ZOOM,
+            data_key: None,
+            widget_id_pool_limit: None,
+        }
+    }
+
+    /// Enable or disable debug drawing.
+    ///
+    /// When enabled, each node is labelled with its identifier (via its `Debug` implementation)
+    /// and each socket is labelled with its index. Labels are positioned using the node widget
+    /// rectangles from the previous update. This is disabled by default.
+    pub fn debug_draw(mut self, enabled: bool) -> Self
+    where
+        N::Item: std::fmt::Debug,
+    {
+        fn debug_label<NI: std::fmt::Debug>(node_id: &NI) -> String {
+            format!("{:?}", node_id)
+        }
+        self.debug_label = match enabled {
+            true => Some(debug_label::<N::Item>),
+            false => None,
+        };
+        self
+    }
+
+    /// Whether or not to emit a `NodeEvent::Removed` for each node that is no longer yielded by the
+    /// graph's `nodes`.
+    ///
+    /// Upon receiving the event, the user should remove the node's position from their `Layout`
+    /// (e.g. via `layout.remove(node_id)`) so that the `Layout` does not accumulate stale entries.
+    /// This is disabled by default.
+    pub fn prune_layout(mut self, prune: bool) -> Self {
+        self.prune_layout = prune;
+        self
+    }
+
+    /// Specify the interaction used to create edges between sockets.
+    ///
+    /// By default, this is `ConnectMode::PressDrag`.
+    pub fn connect_mode(mut self, mode: ConnectMode) -> Self {
+        self.connect_mode = mode;
+        self
+    }
+
+    /// Limit the number of nodes that may be instantiated for the first time during each update.
+    ///
+    /// This spreads the cost of loading very large graphs across multiple frames. New nodes whose
+    /// positions lie within the **Graph**'s rectangle are instantiated first. Nodes that have
+    /// already been instantiated, along with the edges between them, are always yielded. Nodes
+    /// that are deferred, along with their edges, are not yielded until a later update.
+    ///
+    /// By default there is no limit.
+    pub fn max_nodes_per_frame(mut self, max: usize) -> Self {
+        self.max_nodes_per_frame = Some(max);
+        self
+    }
+
     /// Color the **Graph**'s rectangular area with the given color.
     pub fn background_color(mut self, color: Color) -> Self {
         self.style.background_color = Some(color);
         self
     }
+
+    /// Draw the given widget behind the graph's nodes and edges in place of the rectangle colored
+    /// via `Graph::background_color`, e.g. an image or a custom grid.
+    ///
+    /// The widget is sized to fill the graph and is instantiated as `graphics_for` the graph, so
+    /// that all interaction with the background (e.g. box selection and panning) is still
+    /// received by the graph.
+    pub fn background<W>(mut self, widget: W) -> Self
+    where
+        W: 'a + Widget,
+    {
+        let mut widget = Some(widget);
+        let set = move |widget_id, graph_id, rect: Rect, ui: &mut UiCell| {
+            if let Some(widget) = widget.take() {
+                widget
+                    .wh(rect.dim())
+                    .xy(rect.xy())
+                    .parent(graph_id)
+                    .graphics_for(graph_id)
+                    .set(widget_id, ui);
+            }
+        };
+        self.background = Some(Background { set: Box::new(set) });
+        self
+    }
+
+    /// Specify the default styling for edges produced by `Edge::straight_line`.
+    pub fn edge_style(mut self, style: EdgeStyle) -> Self {
+        self.edge_style = style;
+        self
+    }
+
+    /// Specify the styling for labels drawn via `Edge::label`.
+    pub fn edge_label_style(mut self, style: EdgeLabelStyle) -> Self {
+        self.edge_label_style = style;
+        self
+    }
+
+    /// Whether or not to skip instantiating nodes that lie entirely outside of the **Graph**'s
+    /// rectangle.
+    ///
+    /// Each node's size is taken from its widget during the last update in which it was
+    /// instantiated, so nodes that have never been instantiated are never culled. Edges connected
+    /// to culled nodes are also skipped. The node currently capturing the mouse (e.g. while being
+    /// dragged) is never culled. This is disabled by default.
+    pub fn cull_offscreen(mut self, cull: bool) -> Self {
+        self.cull_offscreen = cull;
+        self
+    }
+
+    /// Instantiate edges at the given layer, between the nodes of lower and higher layers.
+    ///
+    /// Each node's layer is assigned via `NodeWidget::layer`. Nodes with a layer less than or equal
+    /// to the edge layer are yielded by `SessionNodes::nodes` and are drawn beneath the edges,
+    /// while nodes with a greater layer are yielded by `SessionEdges::foreground_nodes` and are
+    /// drawn above them. By default, all nodes are drawn beneath the edges.
+    pub fn edge_layer(mut self, layer: i8) -> Self {
+        self.edge_layer = Some(layer);
+        self
+    }
+
+    /// While the given modifier key (e.g. `ModifierKey::SHIFT`) is held, constrain node dragging to
+    /// whichever of the horizontal or vertical axes the mouse moved furthest along.
+    ///
+    /// By default, nodes may be dragged freely.
+    pub fn axis_lock_modifier(mut self, modifier: ModifierKey) -> Self {
+        self.axis_lock_modifier = Some(modifier);
+        self
+    }
+
+    /// Limit the number of edges that the user may connect to each input socket.
+    ///
+    /// When the user attempts to connect an edge to an input socket that already has `capacity`
+    /// edges, the attempt is either rejected or replaces the earliest existing edge(s) depending on
+    /// the given `overflow`. Only edges created by the user are checked. A `capacity` of `0`
+    /// rejects every attempt, even with `InputOverflow::Replace`. By default, input sockets have
+    /// unlimited capacity.
+    pub fn input_capacity(mut self, capacity: usize, overflow: InputOverflow) -> Self {
+        self.input_capacity = Some((capacity, overflow));
+        self
+    }
+
+    /// Reject the user's attempts to create an edge that already exists.
+    ///
+    /// An attempt to create an edge for which an equal edge (as described by the given
+    /// `directionality`) is already yielded by the graph emits an `EdgeEvent::Cancelled` in place
+    /// of the `EdgeEvent::Add`. Likewise, a grabbed edge end may not be reconnected so as to
+    /// duplicate an existing edge. By default, duplicate edges are permitted.
+    pub fn reject_duplicate_edges(mut self, directionality: Directionality) -> Self {
+        self.duplicate_edges = Some(directionality);
+        self
+    }
+
+    /// Specify whether or not the graph's edges are directed. By default, edges are directed.
+    ///
+    /// Edges are always yielded and reported as `(start, end)` pairs from an output socket to an
+    /// input socket. For undirected graphs however, `Edge::straight_line_with_arrow` omits the
+    /// arrowhead, the curves produced by `Edge::bezier` leave each socket toward the other node
+    /// so that an edge looks the same in either direction, and an edge is considered a duplicate
+    /// of its reverse by `Graph::reject_duplicate_edges` regardless of the given `Directionality`.
+    pub fn directed(mut self, directed: bool) -> Self {
+        self.directed = directed;
+        self
+    }
+
+    /// Consult the given function before completing each edge the user attempts to create.
+    ///
+    /// The function is called with the output and input sockets of the edge once the user
+    /// releases (or, with `ConnectMode::ClickClick`, clicks) over a valid socket. If it returns
+    /// `false`, an `EdgeEvent::Cancelled` is emitted in place of `EdgeEvent::Add` and the rejected
+    /// connection is available via `Session::rejected_connection` for the remainder of the update,
+    /// e.g. to draw the target socket in a warning color.
+    ///
+    /// Without a validator, all connections between an output and an input socket are permitted.
+    pub fn validate_connection<F>(mut self, validate: F) -> Self
+    where
+        F: 'a + Fn(NodeSocket<N::Item>, NodeSocket<N::Item>) -> bool,
+    {
+        self.connection_validator = Some(ConnectionValidator { validate: Box::new(validate) });
+        self
+    }
+
+    /// Compare the graph's edges between updates by the identity produced by the given function
+    /// rather than by their start and end sockets.
+    ///
+    /// The function is called with the output and input sockets of each edge yielded by the graph
+    /// and should return the application's identity for the edge (e.g. its index within the
+    /// application's own graph). Edges whose identity is unchanged since the previous update keep
+    /// their index and are updated in place, e.g. when the application reconnects an edge to a
+    /// different socket, while all edges from the first changed identity onward are recreated.
+    ///
+    /// As the **Graph**'s edges carry no data of their own, the function only receives the
+    /// sockets. Any other identity must be looked up from the application's state by those
+    /// sockets, e.g. by a map captured by the closure.
+    ///
+    /// By default, edges are identified by their start and end sockets.
+    pub fn edge_key<F>(mut self, key: F) -> Self
+    where
+        F: 'a + Fn(NodeSocket<N::Item>, NodeSocket<N::Item>) -> u64,
+    {
+        self.edge_key = Some(EdgeKey { key: Box::new(key) });
+        self
+    }
+
+    /// Limit the number of unused `widget::Id`s retained for each type of widget.
+    ///
+    /// The **Graph** keeps a pool of `widget::Id`s for each type of node, edge and annotation
+    /// widget and reuses them from the start of the pool each update. As a result, each pool only
+    /// grows to the greatest number of widgets of its type instantiated within a single update,
+    /// and repeatedly adding and removing nodes does not grow the pools. With a limit, the `Id`s
+    /// beyond the limit that were not used during the last update are released after a peak has
+    /// passed. Note that conrod's `Ui` itself never forgets a `widget::Id`, so a released `Id` is
+    /// replaced by a new one if the pool grows again. By default, no `Id`s are released.
+    pub fn widget_id_pool_limit(mut self, limit: usize) -> Self {
+        self.widget_id_pool_limit = Some(limit);
+        self
+    }
+
+    /// View the graph through the given camera.
+    ///
+    /// The camera may be panned with the arrow keys while the **Graph** is capturing the keyboard,
+    /// by `pan_step` per press or `FAST_PAN_MULTIPLIER` times that while `SHIFT` is held. The
+    /// panned camera is available via `Session::camera`. As the given camera replaces the
+    /// **Graph**'s camera each update, the user should store the camera returned by
+    /// `Session::camera` and pass it back during the next update. Without a camera, the **Graph**
+    /// retains its own, which begins at the centre of the graph.
+    pub fn camera(mut self, camera: Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    /// The distance by which each arrow key press pans the camera.
+    ///
+    /// Defaults to `DEFAULT_PAN_STEP`.
+    pub fn pan_step(mut self, step: Scalar) -> Self {
+        self.pan_step = step;
+        self
+    }
+
+    /// The smallest zoom permitted for the camera.
+    ///
+    /// The camera's zoom is clamped to the range between `min_zoom` and `max_zoom` each update,
+    /// including the zoom of any camera given via `Graph::camera`, so `Session::camera` always
+    /// returns a zoom within the range. If `min_zoom` is greater than `max_zoom` the two are
+    /// swapped. Defaults to `DEFAULT_MIN_ZOOM`.
+    pub fn min_zoom(mut self, zoom: Scalar) -> Self {
+        self.min_zoom = zoom;
+        self
+    }
+
+    /// The largest zoom permitted for the camera.
+    ///
+    /// See `Graph::min_zoom`. Defaults to `DEFAULT_MAX_ZOOM`.
+    pub fn max_zoom(mut self, zoom: Scalar) -> Self {
+        self.max_zoom = zoom;
+        self
+    }
+
+    /// The distance from the mouse within which an edge being created snaps to the nearest socket
+    /// of the opposite type when the user releases (or, with `ConnectMode::ClickClick`, clicks).
+    ///
+    /// If no such socket lies within the radius, an `EdgeEvent::Cancelled` is emitted. A radius of
+    /// `0.0` requires the mouse to lie over the socket itself. Defaults to
+    /// `DEFAULT_SOCKET_SNAP_RADIUS`.
+    pub fn socket_snap_radius(mut self, radius: Scalar) -> Self {
+        self.socket_snap_radius = radius;
+        self
+    }
+
+    /// Show a tooltip above each socket once the mouse has rested over it for the given duration.
+    ///
+    /// The tooltip displays the socket's label as given to `Node::input_labels` or
+    /// `Node::output_labels`, falling back to its type and index for unlabelled sockets. The
+    /// tooltip is drawn above the nodes but passes all input through to the graph, and is hidden
+    /// while a mouse button is held. As the **Graph** is only updated in response to input, the
+    /// tooltip appears during the first update after the delay has elapsed. Tooltips are disabled
+    /// by default.
+    pub fn socket_tooltip_delay(mut self, delay: Duration) -> Self {
+        self.socket_tooltip_delay = Some(delay);
+        self
+    }
+
+    /// Snap dragged nodes to the nearest multiple of the given grid size.
+    ///
+    /// The `to` point of each `NodeEvent::Dragged` is snapped while the `from` point remains the
+    /// position prior to the drag. Snapping is disabled by default and is only enabled for
+    /// positive grid sizes.
+    pub fn snap_to_grid(mut self, grid_size: Scalar) -> Self {
+        self.grid_snap = if grid_size > 0.0 { Some(grid_size) } else { None };
+        self
+    }
+
+    /// Specify the default styling for nodes produced by `NodeContext::styled_widget`.
+    pub fn node_style(mut self, style: node::Style) -> Self {
+        self.node_style = style;
+        self
+    }
+
+    /// Draw a grid over the **Graph**'s background with the given spacing between lines.
+    ///
+    /// The grid lines are fixed in graph space, with a line passing through the centre of the
+    /// graph along each axis.
+    pub fn grid_spacing(mut self, spacing: Scalar) -> Self {
+        self.style.grid_spacing = Some(Some(spacing));
+        self
+    }
+
+    /// Specify the default layout for the input sockets of all nodes.
+    ///
+    /// Applies to the nodes produced by `NodeContext::styled_widget` whose style (as given to
+    /// `Graph::node_style`) does not specify its own input socket layout. As edges are routed to
+    /// the sockets of each node widget, edges follow the layout without further configuration.
+    pub fn input_socket_layout(mut self, layout: SocketLayout) -> Self {
+        self.style.input_socket_layout = Some(layout);
+        self
+    }
+
+    /// Specify the default layout for the output sockets of all nodes.
+    ///
+    /// See `Graph::input_socket_layout`.
+    pub fn output_socket_layout(mut self, layout: SocketLayout) -> Self {
+        self.style.output_socket_layout = Some(layout);
+        self
+    }
+
+    /// Specify the distance the mouse must move while the left button is held before a node is
+    /// dragged or a box selection begins.
+    ///
+    /// Below this threshold a press and release is treated as a click, so nodes are not nudged
+    /// when clicking their buttons. Defaults to `3.0`.
+    pub fn drag_threshold(mut self, threshold: Scalar) -> Self {
+        self.style.drag_threshold = Some(threshold);
+        self
+    }
+
+    /// Color the background grid lines with the given color.
+    pub fn grid_color(mut self, color: Color) -> Self {
+        self.style.grid_color = Some(color);
+        self
+    }
+
+    /// Apply the given set of colors to the graph.
+    ///
+    /// This sets the **Graph**'s background color and makes the colors available to the node
+    /// and edge instantiation stages via `Session::colors`.
+    pub fn colors(mut self, colors: GraphColors) -> Self {
+        self.style.background_color = Some(colors.background);
+        self.colors = Some(colors);
+        self
+    }
 }
 
 impl<'a, N, E> Widget for Graph<'a, N, E>
@@ -797,14 +3831,7 @@ where
     type Event = SessionEvents<N::Item>;
 
     fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
-        let events = VecDeque::new();
-        let nodes = HashMap::new();
-        let node_ids = Vec::new();
-        let edges = Vec::new();
-        let type_widget_ids = HashMap::new();
-        let node_widget_ids = HashMap::new();
-        let widget_id_map = WidgetIdMap { type_widget_ids, node_widget_ids };
-        let shared = Shared { events, nodes, node_ids, edges, widget_id_map };
+        let shared = Shared::new(self.directed);
         State {
             ids: Ids::new(id_gen),
             shared: Arc::new(Mutex::new(shared)),
@@ -817,44 +3844,521 @@ where
 
     fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
         let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
-        let Graph { nodes, edges, layout, .. } = self;
+        let Graph {
+            nodes,
+            edges,
+            mut layout,
+            debug_label,
+            prune_layout,
+            connect_mode,
+            max_nodes_per_frame,
+            colors,
+            edge_style,
+            node_style,
+            edge_label_style,
+            grid_snap,
+            cull_offscreen,
+            edge_layer,
+            axis_lock_modifier,
+            input_capacity,
+            duplicate_edges,
+            directed,
+            connection_validator,
+            edge_key,
+            background,
+            socket_tooltip_delay,
+            camera,
+            pan_step,
+            socket_snap_radius,
+            min_zoom,
+            max_zoom,
+            data_key,
+            widget_id_pool_limit,
+            ..
+        } = self;
         let mut shared = state.shared.lock().unwrap();
 
-        // Reset the WidgetIdMap indices.
-        shared.widget_id_map.reset_indices();
+        // Release excess pooled `widget::Id`s before their indices are reset.
+        if let Some(limit) = widget_id_pool_limit {
+            shared.widget_id_map.shrink_to(limit);
+        }
+
+        // Clear the state of the previous update.
+        shared.reset_per_update();
+        shared.update_count += 1;
+
+        shared.colors = colors;
+        shared.edge_style = edge_style;
+        shared.edge_label_style = edge_label_style;
+        shared.directed = directed;
+        // Edges of undirected graphs are equal to their reverse.
+        let duplicate_edges = match directed {
+            true => duplicate_edges,
+            false => duplicate_edges.map(|_| Directionality::Undirected),
+        };
+        shared.node_style = node_style;
+        shared.socket_layouts = SocketLayouts {
+            input: style.input_socket_layout(&ui.theme),
+            output: style.output_socket_layout(&ui.theme),
+        };
+        let drag_threshold = style.drag_threshold(&ui.theme);
+
+        // Pan the camera with the arrow keys.
+        //
+        // Key presses are only received while the graph itself is capturing the keyboard, so
+        // arrow keys pressed within focused node widgets are left to those widgets.
+        if let Some(camera) = camera {
+            shared.camera = camera;
+        }
+        // Keep the zoom within the permitted range, however the camera was given.
+        shared.camera.zoom = clamp_zoom(shared.camera.zoom, min_zoom, max_zoom);
+        for press in ui.widget_input(id).presses().key() {
+            let step = match press.modifiers.contains(ModifierKey::SHIFT) {
+                true => pan_step * FAST_PAN_MULTIPLIER,
+                false => pan_step,
+            };
+            let [dx, dy] = match press.key {
+                Key::Left => [-step, 0.0],
+                Key::Right => [step, 0.0],
+                Key::Up => [0.0, step],
+                Key::Down => [0.0, -step],
+                _ => continue,
+            };
+            shared.camera.point[0] += dx;
+            shared.camera.point[1] += dy;
+            shared.panned = true;
+        }
+        let camera_point = shared.camera.point;
 
-        // Compare the existing node indices with the new iterator.
-        match conrod::utils::iter_diff(&shared.node_ids, nodes) {
-            Some(diff) => match diff {
-                IterDiff::FirstMismatch(i, mismatch) => {
-                    shared.node_ids.truncate(i);
-                    shared.node_ids.extend(mismatch);
+        // Skip comparing the nodes and edges if they were yielded by an unchanged `GraphData`.
+        let is_data_unchanged = data_key.is_some() && data_key == shared.data_key;
+        shared.data_key = data_key;
+        if !is_data_unchanged {
+            // If pruning, keep track of the previous nodes so that we may detect those removed.
+            let prev_node_ids = match prune_layout {
+                true => shared.node_ids.clone(),
+                false => vec![],
+            };
+
+            // Compare the existing node indices with the new iterator.
+            //
+            // Only the nodes from the first mismatch onward may have appeared during this update.
+            let maybe_new_from = match conrod::utils::iter_diff(&shared.node_ids, nodes) {
+                Some(diff) => match diff {
+                    IterDiff::FirstMismatch(i, mismatch) => {
+                        shared.node_ids.truncate(i);
+                        shared.node_ids.extend(mismatch);
+                        Some(i)
+                    },
+                    IterDiff::Longer(remaining) => {
+                        let i = shared.node_ids.len();
+                        shared.node_ids.extend(remaining);
+                        Some(i)
+                    },
+                    IterDiff::Shorter(total) => {
+                        shared.node_ids.truncate(total);
+                        None
+                    },
                 },
-                IterDiff::Longer(remaining) => {
-                    shared.node_ids.extend(remaining);
+                None => None,
+            };
+
+            // Record the update during which each new node appeared. `shared.nodes` still
+            // describes the nodes of the previous update. Nodes present during the graph's first
+            // update are considered to have always existed.
+            if let (Some(i), true) = (maybe_new_from, shared.update_count > 1) {
+                let update_count = shared.update_count;
+                let Shared { ref node_ids, ref nodes, ref mut node_appearances, .. } = *shared;
+                for &node_id in &node_ids[i..] {
+                    if !nodes.contains_key(&node_id) {
+                        node_appearances.entry(node_id).or_insert(update_count);
+                    }
+                }
+            }
+
+            // Emit a `Removed` event for each node that is no longer present.
+            if !prev_node_ids.is_empty() {
+                let node_ids: HashSet<N::Item> = shared.node_ids.iter().cloned().collect();
+                let mut removed = HashSet::new();
+                for node_id in prev_node_ids {
+                    if !node_ids.contains(&node_id) && removed.insert(node_id) {
+                        let event = Event::Node(NodeEvent::Removed(node_id));
+                        shared.events.push_back(event);
+                    }
+                }
+            }
+
+            // Compare the existing edges with the new iterator.
+            //
+            // Edges are compared by the user's `edge_key` function if there is one, or by their
+            // start and end sockets otherwise.
+            match edge_key {
+                Some(edge_key) => {
+                    let keyed_edges: Vec<_> = edges
+                        .map(|(start, end)| (edge_key.key(start, end), (start, end)))
+                        .collect();
+                    let Shared { ref mut edges, ref mut edge_keys, .. } = *shared;
+                    update_keyed_edges(edges, edge_keys, keyed_edges);
                 },
-                IterDiff::Shorter(total) => {
-                    shared.node_ids.truncate(total);
+                None => {
+                    shared.edge_keys.clear();
+                    match conrod::utils::iter_diff(&shared.edges, edges) {
+                        Some(diff) => match diff {
+                            IterDiff::FirstMismatch(i, mismatch) => {
+                                shared.edges.truncate(i);
+                                shared.edges.extend(mismatch);
+                            },
+                            IterDiff::Longer(remaining) => {
+                                shared.edges.extend(remaining);
+                            },
+                            IterDiff::Shorter(total) => {
+                                shared.edges.truncate(total);
+                            },
+                        },
+                        None => (),
+                    }
                 },
-            },
-            None => (),
+            }
+
+            // Group the edges that share the same start and end nodes.
+            let mut parallel_counts = HashMap::new();
+            let parallel_indices: Vec<usize> = shared.edges
+                .iter()
+                .map(|&(start, end)| {
+                    let count = parallel_counts.entry((start.id, end.id)).or_insert(0);
+                    *count += 1;
+                    *count - 1
+                })
+                .collect();
+            let parallel_edges = parallel_indices
+                .into_iter()
+                .zip(&shared.edges)
+                .map(|(index, &(start, end))| (index, parallel_counts[&(start.id, end.id)]))
+                .collect();
+            shared.parallel_edges = parallel_edges;
+        }
+
+        // Index the sockets of the node widgets from the previous update.
+        {
+            let Shared { ref widget_id_map, ref mut socket_grid, .. } = *shared;
+            socket_grid.rebuild(&widget_id_map.node_widget_ids, ui);
+        }
+
+        // Emit `Hovered` and `Unhovered` events as the mouse enters and leaves node widgets.
+        //
+        // Where multiple node widgets contain the mouse, the last instantiated (and in turn the
+        // top-most) is considered hovered.
+        {
+            let mouse_xy = ui.global_input().current.mouse.xy;
+            let mouse_over_node = {
+                let Shared { ref node_ids, ref widget_id_map, .. } = *shared;
+                node_ids.iter()
+                    .rev()
+                    .find(|node_id| {
+                        widget_id_map.node_widget_ids.get(node_id)
+                            .and_then(|&widget_id| ui.rect_of(widget_id))
+                            .map(|rect| rect.is_over(mouse_xy))
+                            .unwrap_or(false)
+                    })
+                    .cloned()
+            };
+            if mouse_over_node != shared.mouse_over_node {
+                if let Some(node_id) = shared.mouse_over_node {
+                    shared.events.push_back(Event::Node(NodeEvent::Unhovered(node_id)));
+                }
+                if let Some(node_id) = mouse_over_node {
+                    shared.events.push_back(Event::Node(NodeEvent::Hovered(node_id)));
+                }
+                shared.mouse_over_node = mouse_over_node;
+            }
+        }
+
+        // Track the socket under the mouse, restarting the hover duration whenever it changes.
+        {
+            let mouse = &ui.global_input().current.mouse;
+            let hovered = match mouse.buttons.pressed().next() {
+                Some(_) => None,
+                None => shared.socket_at(mouse.xy),
+            };
+            shared.hovered_socket = match (hovered, shared.hovered_socket) {
+                (Some((ty, socket)), Some((prev_ty, prev_socket, since)))
+                    if ty == prev_ty && socket == prev_socket => Some((ty, socket, since)),
+                (Some((ty, socket)), _) => Some((ty, socket, Instant::now())),
+                (None, _) => None,
+            };
+        }
+
+        // Emit a `DoubleClicked` event for each node widget double-clicked since the last update.
+        let double_clicked: Vec<_> = shared.widget_id_map.node_widget_ids
+            .iter()
+            .filter(|&(_, &widget_id)| {
+                ui.widget_input(widget_id).events().any(|event| match event {
+                    event::Widget::DoubleClick(click) => click.button == MouseButton::Left,
+                    _ => false,
+                })
+            })
+            .map(|(&node_id, _)| node_id)
+            .collect();
+        for node_id in double_clicked {
+            shared.events.push_back(Event::Node(NodeEvent::DoubleClicked(node_id)));
+        }
+
+        // Emit a `CollapseToggled` event for each collapse chevron clicked since the last update.
+        let collapse_toggled: Vec<_> = shared.widget_id_map.node_widget_ids
+            .iter()
+            .filter_map(|(&node_id, &widget_id)| {
+                node::collapse_chevron(widget_id, ui).and_then(|(chevron_rect, collapsed)| {
+                    let clicked = ui.widget_input(widget_id)
+                        .clicks()
+                        .left()
+                        .any(|click| chevron_rect.is_over(click.xy));
+                    match clicked {
+                        true => Some((node_id, !collapsed)),
+                        false => None,
+                    }
+                })
+            })
+            .collect();
+        for (node_id, collapsed) in collapse_toggled {
+            let event = NodeEvent::CollapseToggled { node_id, collapsed };
+            shared.events.push_back(Event::Node(event));
         }
 
-        // Compare the existing edges with the new iterator.
-        match conrod::utils::iter_diff(&shared.edges, edges) {
-            Some(diff) => match diff {
-                IterDiff::FirstMismatch(i, mismatch) => {
-                    shared.edges.truncate(i);
-                    shared.edges.extend(mismatch);
+        // Emit a `ContextMenu` event for each right click over the graph or one of its nodes.
+        //
+        // Right clicks are ignored while the left mouse button is in use, so that context menus
+        // cannot interrupt edge creation or dragging.
+        let left_is_down = ui.global_input().current.mouse.buttons.left().is_down();
+        if shared.connecting.is_none() && !left_is_down {
+            let context_menus: Vec<_> = ui.global_input()
+                .events()
+                .ui()
+                .filter_map(|event| match *event {
+                    event::Ui::Click(Some(clicked), event::Click {
+                        button: MouseButton::Right, xy, ..
+                    }) if clicked == id || is_descendant_of(clicked, id, &ui) => {
+                        let target = shared.widget_id_map.node_widget_ids
+                            .iter()
+                            .find(|&(_, &widget_id)| {
+                                clicked == widget_id || is_descendant_of(clicked, widget_id, &ui)
+                            })
+                            .map(|(&node_id, _)| node_id);
+                        Some(Event::ContextMenu { at: xy, target })
+                    },
+                    _ => None,
+                })
+                .collect();
+            shared.events.extend(context_menus);
+        }
+
+        // Step the edge creation state machine with each left mouse button press and release.
+        //
+        // Sockets are found using the node widgets from the previous update.
+        //
+        // Presses captured by a widget within a node (e.g. a button wrapped by a `Node`) are
+        // consumed by that widget and are ignored.
+        let mouse_buttons: Vec<(bool, Point)> = ui.global_input()
+            .events()
+            .ui()
+            .filter_map(|event| match *event {
+                event::Ui::Press(capturing, event::Press {
+                    button: event::Button::Mouse(MouseButton::Left, xy), ..
+                }) => {
+                    let node_ids = &shared.widget_id_map.node_widget_ids;
+                    let consumed = capturing
+                        .map(|w| is_within_node_widget(w, node_ids, &ui))
+                        .unwrap_or(false);
+                    if consumed { None } else { Some((true, xy)) }
                 },
-                IterDiff::Longer(remaining) => {
-                    shared.edges.extend(remaining);
+                event::Ui::Release(_, event::Release {
+                    button: event::Button::Mouse(MouseButton::Left, xy), ..
+                }) => Some((false, xy)),
+                _ => None,
+            })
+            .collect();
+        for (pressed, xy) in mouse_buttons {
+            let socket = shared.socket_at(xy);
+            match (connect_mode, shared.connecting, pressed) {
+                // Begin an edge from the pressed socket.
+                (_, None, true) => if let Some((ty, socket)) = socket {
+                    // Pressing an input socket with a connected edge grabs the end of the most
+                    // recent of its edges for reconnecting.
+                    let grabbed = match ty {
+                        SocketType::Input => shared.edges
+                            .iter()
+                            .rev()
+                            .find(|&&(_, end)| end == socket)
+                            .cloned(),
+                        SocketType::Output => None,
+                    };
+                    shared.connecting_by_press = true;
+                    match grabbed {
+                        Some((start, end)) => {
+                            shared.connecting = Some((SocketType::Output, start));
+                            shared.reconnecting = Some((start, end));
+                        },
+                        None => {
+                            shared.connecting = Some((ty, socket));
+                            shared.events.push_back(Event::Edge(EdgeEvent::AddStart(socket)));
+                        },
+                    }
                 },
-                IterDiff::Shorter(total) => {
-                    shared.edges.truncate(total);
+                // Complete or cancel the edge.
+                (ConnectMode::PressDrag, Some(start), false) |
+                (ConnectMode::ClickClick, Some(start), true) => {
+                    shared.connecting = None;
+
+                    // Snap to the nearest socket of the opposite type within the snap radius.
+                    let (start_type, _) = start;
+                    let socket = match socket {
+                        Some((ty, socket)) if ty != start_type => Some((ty, socket)),
+                        _ => {
+                            let target_type = match start_type {
+                                SocketType::Input => SocketType::Output,
+                                SocketType::Output => SocketType::Input,
+                            };
+                            let radius = socket_snap_radius;
+                            shared.socket_grid.nearest_socket(xy, radius, Some(target_type))
+                        },
+                    };
+
+                    // Complete the reconnection of a grabbed edge end.
+                    if let Some((old_start, old_end)) = shared.reconnecting.take() {
+                        let event = match socket {
+                            Some((SocketType::Input, new_end)) if new_end == old_end => None,
+                            // A grabbed edge end may not be dropped so as to duplicate an edge.
+                            Some((SocketType::Input, new_end))
+                                if is_duplicate(&shared.edges, (old_start, new_end),
+                                                duplicate_edges) => None,
+                            Some((SocketType::Input, new_end)) => {
+                                let is_valid = connection_validator.as_ref()
+                                    .map(|validator| validator.is_valid(old_start, new_end))
+                                    .unwrap_or(true);
+                                if is_valid {
+                                    Some(EdgeEvent::Reconnect { old_start, old_end, new_end })
+                                } else {
+                                    shared.rejected_connection = Some((old_start, new_end));
+                                    None
+                                }
+                            },
+                            _ => Some(EdgeEvent::Remove { start: old_start, end: old_end }),
+                        };
+                        if let Some(event) = event {
+                            shared.events.push_back(Event::Edge(event));
+                        }
+                        continue;
+                    }
+
+                    let (_, origin) = start;
+                    let event = match (start, socket) {
+                        ((SocketType::Output, start), Some((SocketType::Input, end))) |
+                        ((SocketType::Input, end), Some((SocketType::Output, start))) => {
+                            let is_valid = connection_validator.as_ref()
+                                .map(|validator| validator.is_valid(start, end))
+                                .unwrap_or(true);
+                            // The edges already connected to the input socket.
+                            let connected: Vec<_> = shared.edges
+                                .iter()
+                                .cloned()
+                                .filter(|&(_, edge_end)| edge_end == end)
+                                .collect();
+                            if is_duplicate(&shared.edges, (start, end), duplicate_edges) {
+                                EdgeEvent::Cancelled(origin)
+                            } else if !is_valid {
+                                shared.rejected_connection = Some((start, end));
+                                EdgeEvent::Cancelled(origin)
+                            } else {
+                                let (removed, event) =
+                                    connect_input(start, end, origin, &connected, input_capacity);
+                                for event in removed {
+                                    shared.events.push_back(Event::Edge(event));
+                                }
+                                event
+                            }
+                        },
+                        _ => EdgeEvent::Cancelled(origin),
+                    };
+                    shared.events.push_back(Event::Edge(event));
                 },
-            },
-            None => (),
+                _ => (),
+            }
+        }
+
+        // If the left mouse button is no longer down while an edge begun by a press is still in
+        // progress, the release was missed (e.g. the window lost focus mid-drag), so cancel it.
+        if connect_mode == ConnectMode::PressDrag && shared.connecting_by_press {
+            let left_is_down = ui.global_input().current.mouse.buttons.left().is_down();
+            if !left_is_down {
+                // A grabbed edge end is simply left connected to its original socket.
+                let was_reconnecting = shared.reconnecting.take().is_some();
+                if let Some((_, start)) = shared.connecting.take() {
+                    if !was_reconnecting {
+                        shared.events.push_back(Event::Edge(EdgeEvent::Cancelled(start)));
+                    }
+                }
+            }
+        }
+
+        // Find the sockets near the mouse to which the edge being created may be connected.
+        if let Some((start_type, start)) = shared.connecting {
+            let mouse_xy = ui.global_input().current.mouse.xy;
+            let drop_targets: HashSet<_> = shared.socket_grid
+                .sockets_near(mouse_xy, DROP_TARGET_RADIUS)
+                .filter(|&(ty, _, _)| ty != start_type)
+                .filter(|&(ty, socket, _)| {
+                    let (start, end) = match ty {
+                        SocketType::Input => (start, socket),
+                        SocketType::Output => (socket, start),
+                    };
+                    connection_validator.as_ref()
+                        .map(|validator| validator.is_valid(start, end))
+                        .unwrap_or(true)
+                        && !is_duplicate(&shared.edges, (start, end), duplicate_edges)
+                })
+                .map(|(ty, socket, _)| (ty, socket))
+                .collect();
+            shared.drop_targets = drop_targets;
+        }
+
+        // Emit `SocketHovered` and `SocketUnhovered` events as the mouse enters and leaves
+        // sockets, along with a `TargetHovered` event when the entered socket would accept the
+        // edge being created.
+        {
+            let mouse_xy = ui.global_input().current.mouse.xy;
+            let socket_under_mouse = shared.socket_at(mouse_xy);
+            if socket_under_mouse != shared.socket_under_mouse {
+                if let Some((socket_type, socket)) = shared.socket_under_mouse {
+                    let event = EdgeEvent::SocketUnhovered { socket_type, socket };
+                    shared.events.push_back(Event::Edge(event));
+                }
+                if let Some((socket_type, socket)) = socket_under_mouse {
+                    let event = EdgeEvent::SocketHovered { socket_type, socket };
+                    shared.events.push_back(Event::Edge(event));
+                    let is_drop_target = shared.drop_targets.contains(&(socket_type, socket));
+                    if let (true, Some((start_type, start))) = (is_drop_target, shared.connecting) {
+                        let (start, end) = match start_type {
+                            SocketType::Output => (start, socket),
+                            SocketType::Input => (socket, start),
+                        };
+                        let event = EdgeEvent::TargetHovered { start, end };
+                        shared.events.push_back(Event::Edge(event));
+                    }
+                }
+                shared.socket_under_mouse = socket_under_mouse;
+            }
+        }
+
+        // Track whether the socket under the mouse would reject the edge being created, so that
+        // the user is warned before releasing.
+        shared.invalid_target = match (shared.connecting, shared.socket_under_mouse) {
+            (Some(start), Some(target))
+                if target != start && !shared.drop_targets.contains(&target) => Some(target),
+            _ => None,
+        };
+
+        // Forget any unsnapped drag positions once the drag has ended.
+        if grid_snap.is_none() || !ui.global_input().current.mouse.buttons.left().is_down() {
+            shared.unsnapped_drags.clear();
         }
 
         // Use `shared.node_ids` and `shared.edges` to fill `shared.nodes`.
@@ -864,44 +4368,480 @@ where
             let node_id = shared.node_ids[i];
 
             // Get the node position, falling back to 0.0, 0.0 if none was given.
-            let point = layout.map.get(&node_id).map(|&p| p).unwrap_or([0.0; 2]);
+            //
+            // The position from the last update is used unless the `Layout` has since changed.
+            let layout_point = layout.get(node_id).map(|&p| p).unwrap_or([0.0; 2]);
+            let point = match shared.node_points.get(&node_id) {
+                Some(&(prev_layout_point, point)) if prev_layout_point == layout_point => point,
+                _ => layout_point,
+            };
+
+            // Nodes are not dragged while an edge is being created from one of their sockets.
+            let connecting_from_node = shared.connecting
+                .map(|(_, socket)| socket.id == node_id)
+                .unwrap_or(false);
 
             // Check to see if this widget has been dragged since the last update.
             let point = match shared.widget_id_map.node_widget_ids.get(&node_id).map(|&w| w) {
                 None => point,
+                Some(_) if connecting_from_node => point,
                 Some(widget_id) => {
-                    let (dragged_x, dragged_y) = ui.widget_input(widget_id)
+                    // Drags beginning over a node's resize handle resize the node rather than
+                    // moving it.
+                    let resize_handle = node::resize_handle(widget_id, ui);
+                    let is_resize_drag = |origin: Point| {
+                        resize_handle.map(|handle| handle.rect.is_over(origin)).unwrap_or(false)
+                    };
+                    let (resized_w, resized_h) = ui.widget_input(widget_id)
+                        .drags()
+                        .left()
+                        .filter(|d| is_resize_drag(d.origin))
+                        .fold((0.0, 0.0), |(w, h), d| (w + d.delta_xy[0], h - d.delta_xy[1]));
+                    let node_dim = ui.rect_of(widget_id).map(|node_rect| node_rect.dim());
+                    if let (Some(handle), Some(from)) = (resize_handle, node_dim) {
+                        let to = handle.constrain([from[0] + resized_w, from[1] + resized_h]);
+                        if (resized_w, resized_h) != (0.0, 0.0) && to != from {
+                            let event = Event::Node(NodeEvent::Resized { node_id, from, to });
+                            shared.events.push_back(event);
+                        }
+                    }
+
+                    // Movement below the drag threshold is treated as a click.
+                    let mut engaged = is_drag_engaged(shared.drag_engaged, widget_id);
+                    let drags: Vec<_> = ui.widget_input(widget_id)
                         .drags()
                         .left()
-                        .fold((0.0, 0.0), |(x, y), d| (x + d.delta_xy[0], y + d.delta_xy[1]));
+                        .filter(|d| !is_resize_drag(d.origin))
+                        .collect();
+                    let (dragged_x, dragged_y) = drags.iter()
+                        .map(|d| thresholded_delta(d, drag_threshold, &mut engaged))
+                        .fold((0.0, 0.0), |(x, y), [dx, dy]| (x + dx, y + dy));
+                    if !drags.is_empty() {
+                        shared.drag_engaged = Some((widget_id, engaged));
+                    }
+
+                    // Constrain the drag to the dominant axis if the axis lock modifier is held.
+                    let modifiers = ui.global_input().current.modifiers;
+                    let is_axis_locked = axis_lock_modifier
+                        .map(|m| modifiers.contains(m))
+                        .unwrap_or(false);
+                    let (dragged_x, dragged_y) = match is_axis_locked {
+                        false => (dragged_x, dragged_y),
+                        true if dragged_x.abs() < dragged_y.abs() => (0.0, dragged_y),
+                        true => (dragged_x, 0.0),
+                    };
+
+                    // Snap the dragged position to the grid if necessary.
+                    let to = match grid_snap {
+                        None => [point[0] + dragged_x, point[1] + dragged_y],
+                        Some(grid) => {
+                            let unsnapped = shared.unsnapped_drags
+                                .entry(node_id)
+                                .or_insert(point);
+                            unsnapped[0] += dragged_x;
+                            unsnapped[1] += dragged_y;
+                            let snap = |s: Scalar| (s / grid).round() * grid;
+                            [snap(unsnapped[0]), snap(unsnapped[1])]
+                        },
+                    };
 
                     // If dragging would not move the widget, we're done.
-                    if dragged_x == 0.0 && dragged_y == 0.0 {
+                    let point = if to == point {
                         point
                     } else {
-                        let to = [point[0] + dragged_x, point[1] + dragged_y];
+                        if shared.dragging_node != Some(node_id) {
+                            shared.dragging_node = Some(node_id);
+                            let node_event = NodeEvent::DragStarted { node_id, from: point };
+                            shared.events.push_back(Event::Node(node_event));
+                        }
                         let node_event = NodeEvent::Dragged { node_id, from: point, to };
                         let event = Event::Node(node_event);
                         shared.events.push_back(event);
+                        if let LayoutRef::Mut(ref mut layout) = layout {
+                            layout.insert(node_id, to);
+                        }
                         to
+                    };
+
+                    // The drag gesture ends once the left mouse button is released.
+                    let released = ui.widget_input(widget_id)
+                        .releases()
+                        .mouse()
+                        .left()
+                        .next()
+                        .is_some();
+                    if released && shared.dragging_node == Some(node_id) {
+                        shared.dragging_node = None;
+                        let node_event = NodeEvent::DragEnded { node_id, to: point };
+                        shared.events.push_back(Event::Node(node_event));
                     }
+
+                    point
                 },
             };
 
+            let layout_point = layout.get(node_id).map(|&p| p).unwrap_or([0.0; 2]);
+            shared.node_points.insert(node_id, (layout_point, point));
             let node = NodeInner { point };
             shared.nodes.insert(node_id, node);
         }
+        {
+            let Shared { ref nodes, ref mut node_points, ref mut node_appearances, .. } = *shared;
+            node_points.retain(|node_id, _| nodes.contains_key(node_id));
+            node_appearances.retain(|node_id, _| nodes.contains_key(node_id));
+        }
+
+        // Forget the drag gesture of a node that is no longer yielded by the graph.
+        if let Some(node_id) = shared.dragging_node {
+            if !shared.nodes.contains_key(&node_id) {
+                shared.dragging_node = None;
+            }
+        }
+
+        // Determine which nodes are to be instantiated for the first time during this update.
+        {
+            let Shared {
+                ref node_ids,
+                ref nodes,
+                ref mut instantiated,
+                ref mut deferred,
+                ..
+            } = *shared;
+            instantiated.retain(|node_id| nodes.contains_key(node_id));
+            let mut new_node_ids: Vec<_> = node_ids.iter()
+                .cloned()
+                .filter(|node_id| !instantiated.contains(node_id))
+                .collect();
+            if let Some(max) = max_nodes_per_frame {
+                // Prioritise nodes that are visible within the graph's rectangle.
+                let (half_w, half_h) = (rect.w() / 2.0, rect.h() / 2.0);
+                let is_visible = |node_id: &N::Item| {
+                    let p = nodes[node_id].point;
+                    let (x, y) = (p[0] - camera_point[0], p[1] - camera_point[1]);
+                    x.abs() <= half_w && y.abs() <= half_h
+                };
+                new_node_ids.sort_by_key(|node_id| !is_visible(node_id));
+                if new_node_ids.len() > max {
+                    deferred.extend(new_node_ids.drain(max..));
+                }
+            }
+            instantiated.extend(new_node_ids);
+        }
+
+        // Forget the layers of nodes that are no longer present.
+        {
+            let Shared { ref nodes, ref mut node_layers, .. } = *shared;
+            node_layers.retain(|node_id, _| nodes.contains_key(node_id));
+        }
+        shared.edge_layer = edge_layer;
+
+        // Record the dimensions of each node instantiated during the last update.
+        {
+            let Shared { ref nodes, ref widget_id_map, ref mut node_dims, .. } = *shared;
+            node_dims.retain(|node_id, _| nodes.contains_key(node_id));
+            for (&node_id, &widget_id) in &widget_id_map.node_widget_ids {
+                if let Some(node_rect) = ui.rect_of(widget_id) {
+                    node_dims.insert(node_id, node_rect.dim());
+                }
+            }
+        }
+
+        // Cull the nodes that lie entirely outside of the graph's rectangle.
+        let capturing_mouse = ui.global_input().current.widget_capturing_mouse;
+        {
+            let Shared {
+                ref nodes,
+                ref widget_id_map,
+                ref node_dims,
+                ref mut culled,
+                ..
+            } = *shared;
+            if cull_offscreen {
+                let graph_rect = Rect::from_xy_dim(camera_point, rect.dim());
+                for (node_id, node) in nodes {
+                    let is_capturing = widget_id_map.node_widget_ids.get(node_id)
+                        .map(|&w| Some(w) == capturing_mouse)
+                        .unwrap_or(false);
+                    let dim = match node_dims.get(node_id) {
+                        Some(&dim) if !is_capturing => dim,
+                        _ => continue,
+                    };
+                    let node_rect = Rect::from_xy_dim(node.point, dim);
+                    if graph_rect.overlap(node_rect).is_none() {
+                        culled.insert(*node_id);
+                    }
+                }
+            }
+        }
+
+        // Check whether or not one of the node widgets is capturing the mouse.
+        shared.pressing_node = capturing_mouse
+            .map(|w| shared.widget_id_map.node_widget_ids.values().any(|&id| id == w))
+            .unwrap_or(false);
+
+        // Find the edge beneath the node being dragged, if any.
+        let left_down = ui.global_input().current.mouse.buttons.left().is_down();
+        let drag_engaged = shared.drag_engaged;
+        let dragged_node = match (left_down, shared.connecting) {
+            (true, None) => capturing_mouse.and_then(|w| {
+                if !is_drag_engaged(drag_engaged, w) {
+                    return None;
+                }
+                shared.widget_id_map.node_widget_ids
+                    .iter()
+                    .find(|&(_, &id)| id == w)
+                    .map(|(&node_id, _)| node_id)
+            }),
+            _ => None,
+        };
+        let edge_under_drag = dragged_node.and_then(|node_id| {
+            let point = match shared.nodes.get(&node_id) {
+                Some(node) => node.point,
+                None => return None,
+            };
+            let centre = [
+                rect.x() + point[0] - camera_point[0],
+                rect.y() + point[1] - camera_point[1],
+            ];
+            let node_widget_ids = &shared.widget_id_map.node_widget_ids;
+            let mut nearest = None;
+            for &(start, end) in &shared.edges {
+                if start.id == node_id || end.id == node_id {
+                    continue;
+                }
+                let (a, b) = match (node_widget_ids.get(&start.id), node_widget_ids.get(&end.id)) {
+                    (Some(&a), Some(&b)) => (a, b),
+                    _ => continue,
+                };
+                let a = node::socket_rect(a, SocketType::Output, start.socket_index, ui);
+                let b = node::socket_rect(b, SocketType::Input, end.socket_index, ui);
+                let distance = match (a, b) {
+                    (Some(a), Some(b)) => distance_to_segment(centre, a.xy(), b.xy()),
+                    _ => continue,
+                };
+                let is_nearer = nearest.map(|(d, _)| distance < d).unwrap_or(true);
+                if distance <= EDGE_DROP_TOLERANCE && is_nearer {
+                    nearest = Some((distance, (start, end)));
+                }
+            }
+            nearest.map(|(_, edge)| (node_id, edge))
+        });
+
+        // If the node was dropped over an edge, request that the edge be split.
+        let prev_edge_under_drag = shared.edge_under_drag;
+        if let Some((node_id, (start, end))) = prev_edge_under_drag {
+            if dragged_node.is_none() {
+                let event = EdgeEvent::SplitRequested { node_id, start, end };
+                shared.events.push_back(Event::Edge(event));
+            }
+        }
+        shared.edge_under_drag = edge_under_drag;
+
+        // Track the in-progress box selection.
+        //
+        // A box selection begins when the left mouse button is dragged over the graph's
+        // background and ends once the button is released.
+        let graph_input = ui.widget_input(id);
+        let left_is_down = graph_input.mouse()
+            .map(|m| m.buttons.left().is_down())
+            .unwrap_or(false);
+        let mut engaged = is_drag_engaged(shared.drag_engaged, id);
+        let drags: Vec<_> = graph_input.drags().left().collect();
+        if !drags.is_empty() {
+            let latest_engaged = drags.iter()
+                .filter(|drag| thresholded_delta(drag, drag_threshold, &mut engaged) != [0.0; 2])
+                .last()
+                .cloned();
+            shared.drag_engaged = Some((id, engaged));
+            if let (true, Some(drag)) = (left_is_down, latest_engaged) {
+                // Shift the widget-relative corners into the space of the `Layout`.
+                let to_layout = |p: Point| [p[0] + camera_point[0], p[1] + camera_point[1]];
+                let rect = Rect::from_corners(to_layout(drag.origin), to_layout(drag.to));
+                shared.box_selection = Some(rect);
+            }
+        }
+        if !left_is_down {
+            shared.box_selection = None;
+        }
+        // Emit a `BackgroundClicked` event for each left click on the background that did not
+        // begin a box selection.
+        if !engaged {
+            let clicks: Vec<_> = graph_input.clicks()
+                .left()
+                .map(|click| {
+                    let point = [click.xy[0] + camera_point[0], click.xy[1] + camera_point[1]];
+                    Event::BackgroundClicked(point)
+                })
+                .collect();
+            shared.events.extend(clicks);
+        }
+        // The next left drag must exceed the threshold anew. The release is read from the global
+        // input, as the graph itself does not receive mouse input while a node captures it.
+        if !ui.global_input().current.mouse.buttons.left().is_down() {
+            shared.drag_engaged = None;
+        }
+
+        match background {
+            Some(mut background) => (background.set)(state.ids.custom_background, id, rect, ui),
+            None => {
+                let background_color = style.background_color(&ui.theme);
+                widget::Rectangle::fill(rect.dim())
+                    .xy(rect.xy())
+                    .color(background_color)
+                    .parent(id)
+                    .graphics_for(id)
+                    .set(state.ids.background, ui);
+            },
+        }
+
+        // Draw the background grid if a positive spacing was given.
+        if let Some(spacing) = style.grid_spacing(&ui.theme) {
+            if spacing > 0.0 {
+                let grid_color = style.grid_color(&ui.theme);
+                let triangles = grid_triangles(rect, spacing, camera_point);
+                widget::Triangles::single_color(grid_color, triangles)
+                    .with_bounding_rect(rect)
+                    .parent(id)
+                    .graphics_for(id)
+                    .set(state.ids.grid, ui);
+            }
+        }
+
+        // Collect the debug labels for each node and socket using the node widgets from the
+        // previous update, shifted to the node's latest position.
+        let mut debug_labels = vec![];
+        if let Some(debug_label) = debug_label {
+            for node_id in &shared.node_ids {
+                let point = shared.nodes[node_id].point;
+                let node_xy = [
+                    rect.x() + point[0] - camera_point[0],
+                    rect.y() + point[1] - camera_point[1],
+                ];
+                debug_labels.push((debug_label(node_id), node_xy));
+                let widget_id = match shared.widget_id_map.node_widget_ids.get(node_id) {
+                    Some(&widget_id) => widget_id,
+                    None => continue,
+                };
+                let offset = match ui.rect_of(widget_id) {
+                    Some(node_rect) => [node_xy[0] - node_rect.x(), node_xy[1] - node_rect.y()],
+                    None => continue,
+                };
+                if let Some((inputs, outputs)) = node::socket_rects(widget_id, ui) {
+                    for (i, socket_rect) in inputs.enumerate().chain(outputs.enumerate()) {
+                        let xy = socket_rect.shift(offset).xy();
+                        debug_labels.push((format!("{}", i), xy));
+                    }
+                }
+            }
+        }
+
+        // Detect overlapping sockets using the node widgets from the previous update.
+        let mut socket_overlaps = vec![];
+        for &node_id in &shared.node_ids {
+            let widget_id = match shared.widget_id_map.node_widget_ids.get(&node_id) {
+                Some(&widget_id) => widget_id,
+                None => continue,
+            };
+            let overlaps = match node::socket_overlaps(widget_id, ui) {
+                Some(overlaps) => overlaps,
+                None => continue,
+            };
+            for ((a_ty, a_ix), (b_ty, b_ix)) in overlaps {
+                let a = NodeSocket { id: node_id, socket_index: a_ix };
+                let b = NodeSocket { id: node_id, socket_index: b_ix };
+                socket_overlaps.push(((a_ty, a), (b_ty, b)));
+            }
+        }
+        shared.socket_overlaps = socket_overlaps;
+
+        // Describe the hovered socket once the mouse has rested over it for the tooltip delay.
+        let socket_tooltip = match (socket_tooltip_delay, shared.hovered_socket) {
+            (Some(delay), Some((ty, socket, since))) if since.elapsed() >= delay => {
+                shared.widget_id_map.node_widget_ids.get(&socket.id).and_then(|&widget_id| {
+                    let (inputs, outputs) = match node::socket_rects(widget_id, ui) {
+                        Some(rects) => rects,
+                        None => return None,
+                    };
+                    let socket_rect = match ty {
+                        SocketType::Input => inputs.nth(socket.socket_index),
+                        SocketType::Output => outputs.nth(socket.socket_index),
+                    };
+                    let text = node::socket_label(widget_id, ty, socket.socket_index, ui)
+                        .map(|label| label.to_string())
+                        .unwrap_or_else(|| format!("{:?} {}", ty, socket.socket_index));
+                    socket_rect.map(|socket_rect| (text, socket_rect, widget_id))
+                })
+            },
+            _ => None,
+        };
 
-        let background_color = style.background_color(&ui.theme);
-        widget::Rectangle::fill(rect.dim())
-            .xy(rect.xy())
-            .color(background_color)
-            .parent(id)
-            .graphics_for(id)
-            .set(state.ids.background, ui);
+        // The position of the cross indicating that the edge being created may not be connected to
+        // the socket under the mouse.
+        let invalid_target_xy = shared.invalid_target
+            .map(|_| ui.global_input().current.mouse.xy);
 
         // Clear the old node->widget mappings ready for node instantiation.
         shared.widget_id_map.clear_node_mappings();
+        std::mem::drop(shared);
+
+        // Instantiate the debug labels.
+        if state.ids.debug_labels.len() < debug_labels.len() {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| state.ids.debug_labels.resize(debug_labels.len(), id_gen));
+        }
+        for (&(ref label, xy), &label_id) in debug_labels.iter().zip(state.ids.debug_labels.iter()) {
+            widget::Text::new(label)
+                .font_size(DEBUG_LABEL_FONT_SIZE)
+                .color(color::WHITE)
+                .xy(xy)
+                .depth(-1.0)
+                .parent(id)
+                .graphics_for(id)
+                .set(label_id, ui);
+        }
+
+        // Instantiate the socket tooltip above the nodes, passing its input through to the node.
+        if let Some((text, socket_rect, node_widget_id)) = socket_tooltip {
+            let font_size = SOCKET_TOOLTIP_FONT_SIZE;
+            let y = socket_rect.top() + SOCKET_TOOLTIP_PAD * 2.0 + font_size as Scalar / 2.0;
+            widget::Text::new(&text)
+                .font_size(font_size)
+                .color(color::WHITE)
+                .x_y(socket_rect.x(), y)
+                .depth(-3.0)
+                .parent(id)
+                .graphics_for(node_widget_id)
+                .set(state.ids.socket_tooltip, ui);
+            if let Some(text_rect) = ui.rect_of(state.ids.socket_tooltip) {
+                let background_rect = text_rect.pad(-SOCKET_TOOLTIP_PAD);
+                widget::Rectangle::fill(background_rect.dim())
+                    .xy(background_rect.xy())
+                    .color(color::rgba(0.0, 0.0, 0.0, 0.8))
+                    .depth(-2.0)
+                    .parent(id)
+                    .graphics_for(node_widget_id)
+                    .set(state.ids.socket_tooltip_background, ui);
+            }
+        }
+
+        // Instantiate a red cross at the mouse above the nodes while over an invalid target.
+        if let Some(xy) = invalid_target_xy {
+            let half = INVALID_TARGET_INDICATOR_SIZE / 2.0;
+            let thickness = INVALID_TARGET_INDICATOR_THICKNESS;
+            let (x, y) = (xy[0], xy[1]);
+            let mut triangles = path::triangles(vec![[x - half, y - half], [x + half, y + half]],
+                                                thickness, Join::Miter);
+            triangles.extend(path::triangles(vec![[x - half, y + half], [x + half, y - half]],
+                                             thickness, Join::Miter));
+            let dim = INVALID_TARGET_INDICATOR_SIZE + thickness;
+            let bounding_rect = Rect::from_xy_dim(xy, [dim, dim]);
+            widget::Triangles::single_color(color::RED, triangles)
+                .with_bounding_rect(bounding_rect)
+                .depth(-3.0)
+                .parent(id)
+                .graphics_for(id)
+                .set(state.ids.invalid_target_indicator, ui);
+        }
 
         let graph_id = id;
         let shared = Arc::downgrade(&state.shared);
@@ -909,3 +4849,338 @@ where
         SessionEvents { session }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A simple deterministic generator of values within `0.0..1.0`.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> Scalar {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 11) as Scalar / (1u64 << 53) as Scalar
+        }
+    }
+
+    // The distance from the given point to the nearest point within the given rectangle.
+    fn distance_to_rect(xy: Point, rect: &Rect) -> Scalar {
+        let (l, r, b, t) = rect.l_r_b_t();
+        let dx = (l - xy[0]).max(xy[0] - r).max(0.0);
+        let dy = (b - xy[1]).max(xy[1] - t).max(0.0);
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    #[test]
+    fn socket_grid_matches_brute_force_scan() {
+        // Scatter non-overlapping sockets over several grid cells, including negative ones.
+        let mut rng = Lcg(1);
+        let mut sockets = vec![];
+        let mut grid = SocketGrid { cells: HashMap::new() };
+        for i in 0..20 {
+            for j in 0..20 {
+                let x = -200.0 + i as Scalar * 20.0 + rng.next() * 10.0;
+                let y = -200.0 + j as Scalar * 20.0 + rng.next() * 10.0;
+                let rect = Rect::from_corners([x, y], [x + 8.0, y + 8.0]);
+                let ty = if (i + j) % 2 == 0 { SocketType::Input } else { SocketType::Output };
+                let socket = NodeSocket { id: i * 20 + j, socket_index: j };
+                grid.insert(ty, socket, rect);
+                sockets.push((ty, socket, rect));
+            }
+        }
+
+        let radius = 16.0;
+        for _ in 0..1000 {
+            let xy = [-220.0 + rng.next() * 440.0, -220.0 + rng.next() * 440.0];
+
+            // Socket rectangles do not overlap, so at most one may contain the point.
+            let expected = sockets.iter()
+                .find(|&&(_, _, ref rect)| rect.is_over(xy))
+                .map(|&(ty, socket, _)| (ty, socket));
+            assert_eq!(grid.socket_at(xy), expected);
+
+            // Compare distances, as multiple sockets may be equally near.
+            for &socket_type in &[None, Some(SocketType::Input), Some(SocketType::Output)] {
+                let nearest = sockets.iter()
+                    .filter(|&&(ty, _, _)| socket_type.map(|t| t == ty).unwrap_or(true))
+                    .map(|&(_, _, ref rect)| distance_to_rect(xy, rect))
+                    .filter(|&d| d <= radius)
+                    .fold(None, |min: Option<Scalar>, d| Some(min.map_or(d, |m| m.min(d))));
+                let found = grid.nearest_socket(xy, radius, socket_type).map(|(ty, socket)| {
+                    let &(_, _, ref rect) = sockets.iter()
+                        .find(|&&(t, s, _)| t == ty && s == socket)
+                        .unwrap();
+                    distance_to_rect(xy, rect)
+                });
+                assert_eq!(found, nearest);
+            }
+        }
+    }
+
+    #[test]
+    fn one_update_interaction_is_not_carried_into_the_next() {
+        let mut shared: Shared<usize> = Shared::new(true);
+        let socket = NodeSocket { id: 0, socket_index: 0 };
+        let target = NodeSocket { id: 1, socket_index: 0 };
+
+        // The first update: a node is pressed while an edge is being created and the camera is
+        // panned, emitting a single event.
+        shared.reset_per_update();
+        shared.hovered_node = Some(0);
+        shared.pressing_node = true;
+        shared.panned = true;
+        shared.connecting = Some((SocketType::Output, socket));
+        shared.camera = Camera::new([20.0, 0.0], 1.0);
+        shared.rejected_connection = Some((socket, target));
+        shared.drop_targets.insert((SocketType::Input, target));
+        shared.events.push_back(Event::Node(NodeEvent::DoubleClicked(0)));
+        let emitted: Vec<_> = shared.events.drain(..).collect();
+        assert_eq!(emitted, vec![Event::Node(NodeEvent::DoubleClicked(0))]);
+
+        // The next, idle update: nothing is re-emitted and transient state is cleared, while the
+        // edge being created and the camera persist.
+        shared.reset_per_update();
+        assert!(shared.events.is_empty());
+        assert_eq!(shared.hovered_node, None);
+        assert!(!shared.pressing_node);
+        assert!(!shared.panned);
+        assert_eq!(shared.rejected_connection, None);
+        assert!(shared.drop_targets.is_empty());
+        assert_eq!(shared.connecting, Some((SocketType::Output, socket)));
+        assert_eq!(shared.camera, Camera::new([20.0, 0.0], 1.0));
+    }
+
+    #[test]
+    fn socket_at_point_finds_sockets_of_known_nodes() {
+        let (border, socket_length) = (6.0, 10.0);
+        // Node 0 has two outputs on its right and node 1 has three inputs on its left.
+        let nodes = [
+            (0, Rect::from_xy_dim([-100.0, 0.0], [100.0, 60.0]), SocketType::Output, 2),
+            (1, Rect::from_xy_dim([100.0, 0.0], [100.0, 60.0]), SocketType::Input, 3),
+        ];
+        let point = |node_index: usize, socket_index| {
+            let (_, rect, ty, n) = nodes[node_index];
+            let layout = match ty {
+                SocketType::Input => SocketLayout::inputs_on_left(),
+                SocketType::Output => SocketLayout::outputs_on_right(),
+            };
+            node::socket_point(rect, border, socket_length, socket_index, n, layout)
+        };
+        let mut shared: Shared<usize> = Shared::new(true);
+        for (node_index, &(id, _, ty, n)) in nodes.iter().enumerate() {
+            for socket_index in 0..n {
+                let xy = point(node_index, socket_index);
+                let rect = Rect::from_xy_dim(xy, [border, socket_length]);
+                shared.socket_grid.insert(ty, NodeSocket { id, socket_index }, rect);
+            }
+        }
+        let shared = Arc::new(Mutex::new(shared));
+        let session = Session { graph_id: widget::Id::new(0), shared: Arc::downgrade(&shared) };
+
+        // Positions over a socket find that socket.
+        let output = (SocketType::Output, NodeSocket { id: 0, socket_index: 1 });
+        assert_eq!(session.socket_at_point(point(0, 1), 0.0), Some(output));
+        let input = (SocketType::Input, NodeSocket { id: 1, socket_index: 2 });
+        assert_eq!(session.socket_at_point(point(1, 2), 0.0), Some(input));
+
+        // Positions beside a socket find it only within the radius. The input's rectangle spans
+        // half the border either side of its centre, so this position lies 10.0 from it.
+        let [x, y] = point(1, 2);
+        let beside = [x - border / 2.0 - 10.0, y];
+        assert_eq!(session.socket_at_point(beside, 16.0), Some(input));
+        assert_eq!(session.socket_at_point(beside, 5.0), None);
+
+        // Positions far from every socket find nothing.
+        assert_eq!(session.socket_at_point([0.0, 200.0], 16.0), None);
+    }
+
+    #[test]
+    fn thresholded_drag_moves_node_by_cursor_displacement() {
+        let node = widget::Id::new(1);
+        let other_node = widget::Id::new(2);
+        let threshold = 3.0;
+        let origin = [10.0, 20.0];
+        let mut drag_engaged = None;
+        let mut moved = [0.0; 2];
+        let mut from = origin;
+        // One drag event per update, with a growing `total_delta_xy`.
+        for &[x, y] in &[[1.0, 0.0], [2.0, 1.0], [5.0, 2.0], [9.0, 4.0], [14.0, 7.0]] {
+            let to = [origin[0] + x, origin[1] + y];
+            let drag = event::Drag {
+                button: MouseButton::Left,
+                origin,
+                from,
+                to,
+                delta_xy: [to[0] - from[0], to[1] - from[1]],
+                total_delta_xy: [x, y],
+                modifiers: ModifierKey::NO_MODIFIER,
+            };
+            let mut engaged = is_drag_engaged(drag_engaged, node);
+            let [dx, dy] = thresholded_delta(&drag, threshold, &mut engaged);
+            drag_engaged = Some((node, engaged));
+            moved = [moved[0] + dx, moved[1] + dy];
+            from = to;
+        }
+        assert_eq!(moved, [14.0, 7.0]);
+        // The engagement belongs to the gesture over the dragged node alone.
+        assert!(is_drag_engaged(drag_engaged, node));
+        assert!(!is_drag_engaged(drag_engaged, other_node));
+        assert!(!is_drag_engaged(None, node));
+    }
+
+    #[test]
+    fn grid_lines_shift_with_the_camera() {
+        assert_eq!(grid_line_offsets(50.0, 20.0, 0.0), vec![-40.0, -20.0, 0.0, 20.0, 40.0]);
+        // Moving the camera right by 5.0 moves the lines left by 5.0, keeping them at multiples
+        // of the spacing within the `Layout`.
+        assert_eq!(grid_line_offsets(50.0, 20.0, 5.0), vec![-45.0, -25.0, -5.0, 15.0, 35.0]);
+        // Moving by a whole spacing leaves the lines where they were.
+        assert_eq!(grid_line_offsets(50.0, 20.0, 20.0), vec![-40.0, -20.0, 0.0, 20.0, 40.0]);
+    }
+
+    #[test]
+    fn parallel_straight_lines_are_offset_symmetrically() {
+        let (start, end) = ([0.0, 0.0], [100.0, 0.0]);
+        let first = parallel_offset(0, 2, PARALLEL_EDGE_SPACING);
+        let second = parallel_offset(1, 2, PARALLEL_EDGE_SPACING);
+        assert_eq!(first, -PARALLEL_EDGE_SPACING / 2.0);
+        assert_eq!(second, PARALLEL_EDGE_SPACING / 2.0);
+        let a = offset_segment(start, end, first);
+        let b = offset_segment(start, end, second);
+        assert!(a != b);
+        assert_eq!(a, ([0.0, -10.0], [100.0, -10.0]));
+        assert_eq!(b, ([0.0, 10.0], [100.0, 10.0]));
+        // A lone edge is not offset.
+        assert_eq!(offset_segment(start, end, parallel_offset(0, 1, 20.0)), (start, end));
+    }
+
+    #[test]
+    fn zoom_is_clamped_to_bounds() {
+        let (min_zoom, max_zoom) = (0.5, 2.0);
+        assert_eq!(clamp_zoom(0.1, min_zoom, max_zoom), min_zoom);
+        assert_eq!(clamp_zoom(min_zoom, min_zoom, max_zoom), min_zoom);
+        assert_eq!(clamp_zoom(1.0, min_zoom, max_zoom), 1.0);
+        assert_eq!(clamp_zoom(max_zoom, min_zoom, max_zoom), max_zoom);
+        assert_eq!(clamp_zoom(8.0, min_zoom, max_zoom), max_zoom);
+        let camera = Camera::new([10.0, -10.0], 8.0).clamp_zoom(min_zoom, max_zoom);
+        assert_eq!(camera.zoom(), max_zoom);
+        assert_eq!(camera.point(), [10.0, -10.0]);
+    }
+
+    #[test]
+    fn unchanged_edge_key_keeps_index_and_widget_id() {
+        let mut ui = conrod::UiBuilder::new([400.0, 400.0]).build();
+        let mut generator = ui.widget_id_generator();
+        let mut widget_id_map: WidgetIdMap<usize> = WidgetIdMap::default();
+        let socket = |id, socket_index| NodeSocket { id, socket_index };
+        let (mut edges, mut edge_keys) = (vec![], vec![]);
+
+        // The first update, followed by the edges' instantiation.
+        let first = vec![(1, (socket(0, 0), socket(1, 0))), (2, (socket(1, 0), socket(2, 0)))];
+        update_keyed_edges(&mut edges, &mut edge_keys, first);
+        widget_id_map.reset_indices();
+        let first_ids: Vec<_> = edges.iter()
+            .map(|_| widget_id_map.next_id_for_edge::<widget::Line>(&mut generator))
+            .collect();
+
+        // The application reconnects the first edge to another socket without changing its key.
+        let second = vec![(1, (socket(0, 1), socket(1, 0))), (2, (socket(1, 0), socket(2, 0)))];
+        update_keyed_edges(&mut edges, &mut edge_keys, second);
+        widget_id_map.reset_indices();
+        let second_ids: Vec<_> = edges.iter()
+            .map(|_| widget_id_map.next_id_for_edge::<widget::Line>(&mut generator))
+            .collect();
+
+        assert_eq!(edge_keys, vec![1, 2]);
+        assert_eq!(edges[0], (socket(0, 1), socket(1, 0)));
+        assert_eq!(edges[1], (socket(1, 0), socket(2, 0)));
+        assert_eq!(first_ids, second_ids);
+    }
+
+    #[test]
+    fn zero_input_capacity_cancels_regardless_of_overflow() {
+        let socket = |id, socket_index| NodeSocket { id, socket_index };
+        let (start, end) = (socket(0usize, 0), socket(1, 0));
+        let connected = vec![(socket(2, 0), end)];
+        for &overflow in &[InputOverflow::Reject, InputOverflow::Replace] {
+            let capacity = Some((0, overflow));
+            let expected = (vec![], EdgeEvent::Cancelled(start));
+            assert_eq!(connect_input(start, end, start, &[], capacity), expected);
+            assert_eq!(connect_input(start, end, start, &connected, capacity), expected);
+        }
+    }
+
+    #[test]
+    fn single_input_capacity_replaces_the_existing_edge() {
+        let socket = |id, socket_index| NodeSocket { id, socket_index };
+        let (start, end) = (socket(0usize, 0), socket(1, 0));
+        let capacity = Some((1, InputOverflow::Replace));
+        assert_eq!(
+            connect_input(start, end, start, &[], capacity),
+            (vec![], EdgeEvent::Add { start, end })
+        );
+        let existing = (socket(2, 0), end);
+        assert_eq!(
+            connect_input(start, end, start, &[existing], capacity),
+            (vec![EdgeEvent::Remove { start: existing.0, end }], EdgeEvent::Add { start, end })
+        );
+    }
+
+    #[test]
+    fn set_zoom_clamps_to_bounds() {
+        let mut camera = Camera::new([0.0; 2], 1.0);
+        camera.set_zoom(10.0, DEFAULT_MIN_ZOOM, DEFAULT_MAX_ZOOM);
+        assert_eq!(camera.zoom(), DEFAULT_MAX_ZOOM);
+        camera.set_zoom(0.0, DEFAULT_MIN_ZOOM, DEFAULT_MAX_ZOOM);
+        assert_eq!(camera.zoom(), DEFAULT_MIN_ZOOM);
+        camera.set_zoom(2.0, DEFAULT_MIN_ZOOM, DEFAULT_MAX_ZOOM);
+        assert_eq!(camera.zoom(), 2.0);
+    }
+
+    #[test]
+    fn graph_update_clamps_the_given_camera_zoom() {
+        let mut ui = conrod::UiBuilder::new([400.0, 400.0]).build();
+        let id = ui.widget_id_generator().next();
+        let layout: Layout<usize> = Layout::from(HashMap::new());
+        let edges: Vec<(NodeSocket<usize>, NodeSocket<usize>)> = vec![];
+        let camera = {
+            let ui = &mut ui.set_widgets();
+            let session = Graph::new(vec![0usize], edges, &layout)
+                .camera(Camera::new([10.0, 0.0], 8.0))
+                .min_zoom(0.5)
+                .max_zoom(2.0)
+                .wh([400.0, 400.0])
+                .set(id, ui);
+            session.camera()
+        };
+        assert_eq!(camera, Camera::new([10.0, 0.0], 2.0));
+    }
+
+    #[test]
+    fn swapped_zoom_bounds_are_reordered() {
+        assert_eq!(clamp_zoom(0.1, 2.0, 0.5), 0.5);
+        assert_eq!(clamp_zoom(1.0, 2.0, 0.5), 1.0);
+        assert_eq!(clamp_zoom(8.0, 2.0, 0.5), 2.0);
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    extern crate petgraph;
+    extern crate serde_json;
+
+    use self::petgraph::graph::NodeIndex;
+    use super::*;
+
+    #[test]
+    fn layout_json_round_trip() {
+        let mut layout = Layout::from(HashMap::new());
+        layout.insert(NodeIndex::new(0), [-100.0, 50.5]);
+        layout.insert(NodeIndex::new(1), [0.0, 0.0]);
+        layout.insert(NodeIndex::new(7), [250.25, -3.0]);
+        let json = serde_json::to_string(&layout).unwrap();
+        let deserialized: Layout<NodeIndex> = serde_json::from_str(&json).unwrap();
+        assert_eq!(layout, deserialized);
+    }
+}
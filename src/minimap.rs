@@ -0,0 +1,194 @@
+//! A scaled-down overview of a graph's nodes along with the region visible through its camera.
+//!
+//! The `Minimap` reads the same `Layout` and `Camera` as the **Graph** widget. Dragging the
+//! rectangle describing the visible region produces a new camera which the user may pass to
+//! `Graph::camera`.
+
+use conrod::{color, widget, Color, Colorable, Dimensions, Positionable, Widget};
+use conrod::position::Rect;
+use conrod::widget::primitive::shape::triangles::Triangle;
+use super::{Camera, Layout, NodeId};
+
+/// A scaled-down overview of all nodes within a `Layout` with a draggable viewport indicator.
+#[derive(Clone, Debug, WidgetCommon)]
+pub struct Minimap<'a, NI: 'a + NodeId> {
+    /// Data necessary and common for all widget builder types.
+    #[conrod(common_builder)]
+    pub common: widget::CommonBuilder,
+    /// Unique styling for the **Minimap**.
+    pub style: Style,
+    /// The position of each node.
+    pub layout: &'a Layout<NI>,
+    /// The camera through which the graph is currently viewed.
+    pub camera: Camera,
+    /// The dimensions of the graph widget viewed through the camera.
+    pub view_dim: Dimensions,
+}
+
+/// Unique styling for the **Minimap** widget.
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    /// Color of the minimap's background.
+    #[conrod(default = "color::rgba(0.0, 0.0, 0.0, 0.5)")]
+    pub color: Option<Color>,
+    /// Color of the boxes representing each node.
+    #[conrod(default = "color::rgba(1.0, 1.0, 1.0, 0.5)")]
+    pub node_color: Option<Color>,
+    /// Color of the outline of the region visible through the camera.
+    #[conrod(default = "color::WHITE")]
+    pub viewport_color: Option<Color>,
+    /// The dimensions of each node's box in graph space.
+    ///
+    /// As a `Layout` only describes the position of each node, all nodes are represented by
+    /// boxes of the same size.
+    #[conrod(default = "[100.0, 60.0]")]
+    pub node_dim: Option<Dimensions>,
+}
+
+widget_ids! {
+    struct Ids {
+        background,
+        nodes,
+        viewport,
+    }
+}
+
+/// Unique state for the `Minimap`.
+pub struct State {
+    ids: Ids,
+}
+
+impl<'a, NI> Minimap<'a, NI>
+where
+    NI: NodeId,
+{
+    /// Begin building a new **Minimap** of the given `layout` viewed through the given `camera`.
+    ///
+    /// `view_dim` is the size of the **Graph** widget, describing the size of the region visible
+    /// through the camera.
+    pub fn new(layout: &'a Layout<NI>, camera: Camera, view_dim: Dimensions) -> Self {
+        Minimap {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            layout,
+            camera,
+            view_dim,
+        }
+    }
+
+    /// Specify the color of the minimap's background.
+    pub fn color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    /// Specify the color of the boxes representing each node.
+    pub fn node_color(mut self, color: Color) -> Self {
+        self.style.node_color = Some(color);
+        self
+    }
+
+    /// Specify the color of the outline of the region visible through the camera.
+    pub fn viewport_color(mut self, color: Color) -> Self {
+        self.style.viewport_color = Some(color);
+        self
+    }
+
+    /// Specify the dimensions of each node's box in graph space.
+    pub fn node_dim(mut self, dim: Dimensions) -> Self {
+        self.style.node_dim = Some(dim);
+        self
+    }
+}
+
+impl<'a, NI> Widget for Minimap<'a, NI>
+where
+    NI: NodeId,
+{
+    type State = State;
+    type Style = Style;
+    /// The camera produced by dragging the viewport, if it was dragged since the last update.
+    type Event = Option<Camera>;
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State { ids: Ids::new(id_gen) }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style.clone()
+    }
+
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+        let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
+        let Minimap { layout, camera, view_dim, .. } = self;
+        let node_dim = style.node_dim(&ui.theme);
+
+        // The region of the graph visible through the camera and the box of each node, both in
+        // graph space.
+        let viewport = Rect::from_xy_dim(camera.point(), view_dim);
+        let node_rects: Vec<Rect> = layout.values()
+            .map(|&point| Rect::from_xy_dim(point, node_dim))
+            .collect();
+
+        // Fit the nodes and the viewport within the minimap, preserving their aspect ratio.
+        let bounds = node_rects.iter().fold(viewport, |bounds, node_rect| {
+            let (l, r, b, t) = bounds.l_r_b_t();
+            let (nl, nr, nb, nt) = node_rect.l_r_b_t();
+            Rect::from_corners([l.min(nl), b.min(nb)], [r.max(nr), t.max(nt)])
+        });
+        let scale = (rect.w() / bounds.w()).min(rect.h() / bounds.h());
+        let to_minimap = |graph_rect: &Rect| {
+            let x = rect.x() + (graph_rect.x() - bounds.x()) * scale;
+            let y = rect.y() + (graph_rect.y() - bounds.y()) * scale;
+            Rect::from_xy_dim([x, y], [graph_rect.w() * scale, graph_rect.h() * scale])
+        };
+        let viewport_rect = to_minimap(&viewport);
+
+        // Drags beginning over the viewport move the camera.
+        let viewport_relative = viewport_rect.shift([-rect.x(), -rect.y()]);
+        let (dx, dy) = ui.widget_input(id)
+            .drags()
+            .left()
+            .filter(|drag| viewport_relative.is_over(drag.origin))
+            .fold((0.0, 0.0), |(x, y), drag| (x + drag.delta_xy[0], y + drag.delta_xy[1]));
+        let event = match (dx, dy) != (0.0, 0.0) && scale > 0.0 {
+            false => None,
+            true => {
+                let point = camera.point();
+                let point = [point[0] + dx / scale, point[1] + dy / scale];
+                Some(Camera::new(point, camera.zoom()))
+            },
+        };
+
+        let color = style.color(&ui.theme);
+        widget::Rectangle::fill(rect.dim())
+            .xy(rect.xy())
+            .color(color)
+            .graphics_for(id)
+            .parent(id)
+            .set(state.ids.background, ui);
+
+        let node_color = style.node_color(&ui.theme);
+        let triangles: Vec<Triangle<_>> = node_rects.iter()
+            .flat_map(|node_rect| {
+                let (a, b) = widget::primitive::shape::rectangle::triangles(to_minimap(node_rect));
+                vec![a, b]
+            })
+            .collect();
+        widget::Triangles::single_color(node_color, triangles)
+            .with_bounding_rect(rect)
+            .graphics_for(id)
+            .parent(id)
+            .set(state.ids.nodes, ui);
+
+        let viewport_color = style.viewport_color(&ui.theme);
+        widget::Rectangle::outline(viewport_rect.dim())
+            .xy(viewport_rect.xy())
+            .color(viewport_color)
+            .graphics_for(id)
+            .parent(id)
+            .set(state.ids.viewport, ui);
+
+        event
+    }
+}
@@ -1,7 +1,9 @@
-use conrod::{self, widget, color, Color, Point, Positionable, Scalar, Sizeable, Widget, Ui};
+use conrod::{self, widget, color, Color, Colorable, FontSize, Point, Positionable, Scalar, Sizeable, Widget, Ui};
+use conrod::Dimensions;
 use conrod::position::{Axis, Direction, Range, Rect};
 use conrod::widget::primitive::shape::triangles::{ColoredPoint, Triangle};
 use std::iter::once;
+use super::GraphColors;
 use std::ops::{Deref, DerefMut};
 
 /// A widget that acts as a convenience container for some `Node`'s unique widgets.
@@ -20,10 +22,50 @@ pub struct Node<W> {
     pub inputs: usize,
     /// The number of output sockets on the node.
     pub outputs: usize,
+    /// The aspect ratio (width / height) of the wrapped widget to preserve, if any.
+    pub aspect_ratio: Option<Scalar>,
+    /// Labels displayed beside each input socket.
+    pub input_labels: Vec<String>,
+    /// Labels displayed beside each output socket.
+    pub output_labels: Vec<String>,
+    /// Colors for each input socket, overriding the style's `socket_color`.
+    pub input_socket_colors: Vec<Color>,
+    /// Colors for each output socket, overriding the style's `socket_color`.
+    pub output_socket_colors: Vec<Color>,
+    /// Concentric outlines drawn around the node, innermost first, described by their thickness
+    /// and color.
+    pub status_outlines: Vec<(Scalar, Color)>,
+    /// A color by which the node's fill, border and socket colors are multiplied, if any.
+    pub tint: Option<Color>,
+    /// Whether or not a handle for resizing the node is drawn in its bottom right corner.
+    pub resizable: bool,
+    /// Whether or not the node is collapsed, if it is collapsible.
+    pub collapsed: Option<bool>,
+    /// The zoom below which the inner widget and socket labels are not instantiated, if any.
+    pub lod_threshold: Option<Scalar>,
+    /// The zoom of the camera through which the node is viewed.
+    pub zoom: Scalar,
+    /// The number of updates over which the node fades in after first appearing, if any.
+    pub appear_duration: Option<u64>,
+    /// The number of updates since the node first appeared, if it appeared after the graph's
+    /// first update.
+    pub age: Option<u64>,
 }
 
 pub const DEFAULT_BORDER_THICKNESS: Scalar = 6.0;
 pub const DEFAULT_SOCKET_LENGTH: Scalar = DEFAULT_BORDER_THICKNESS;
+/// The number of triangles used to approximate each `SocketShape::Circle` socket.
+pub const SOCKET_CIRCLE_RESOLUTION: usize = 12;
+/// The number of translucent rectangles used to approximate the blur of a node's `Shadow`.
+pub const SHADOW_LAYERS: usize = 4;
+/// The number of segments used to approximate each corner when `Style::corner_radius` is set.
+pub const CORNER_RESOLUTION: usize = 6;
+/// The width and height of the triangular handle drawn in the corner of resizable nodes.
+pub const RESIZE_HANDLE_SIZE: Scalar = 12.0;
+/// The height of a collapsed node's title bar, excluding its border.
+pub const COLLAPSED_HEIGHT: Scalar = 16.0;
+/// The width and height of the chevron drawn in the top right corner of collapsible nodes.
+pub const COLLAPSE_CHEVRON_SIZE: Scalar = 10.0;
 
 /// Unique styling for the **BorderedRectangle** widget.
 #[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
@@ -46,11 +88,57 @@ pub struct Style {
     #[conrod(default = "color::DARK_GREY")]
     pub socket_color: Option<Color>,
     /// Default layout for input sockets.
-    #[conrod(default = "SocketLayout { side: SocketSide::Left, direction: Direction::Backwards }")]
+    #[conrod(default = "SocketLayout::inputs_on_left()")]
     pub input_socket_layout: Option<SocketLayout>,
     /// Default layout for node output sockets.
-    #[conrod(default = "SocketLayout { side: SocketSide::Right, direction: Direction::Backwards }")]
+    #[conrod(default = "SocketLayout::outputs_on_right()")]
     pub output_socket_layout: Option<SocketLayout>,
+    /// Color of the socket labels.
+    #[conrod(default = "color::WHITE")]
+    pub socket_label_color: Option<Color>,
+    /// The shape used to draw each socket.
+    #[conrod(default = "SocketShape::Rectangle")]
+    pub socket_shape: Option<SocketShape>,
+    /// Font size of the socket labels.
+    #[conrod(default = "10")]
+    pub socket_label_font_size: Option<FontSize>,
+    /// The radius of the node's corners, if they are rounded.
+    #[conrod(default = "None")]
+    pub corner_radius: Option<Option<Scalar>>,
+    /// The drop shadow drawn behind the node, if any.
+    #[conrod(default = "None")]
+    pub shadow: Option<Option<Shadow>>,
+    /// The smallest size to which the node may be resized via its resize handle.
+    #[conrod(default = "[RESIZE_HANDLE_SIZE * 2.0, RESIZE_HANDLE_SIZE * 2.0]")]
+    pub min_size: Option<Dimensions>,
+    /// The largest size to which the node may be resized via its resize handle, if any.
+    #[conrod(default = "None")]
+    pub max_size: Option<Option<Dimensions>>,
+}
+
+/// A soft drop shadow drawn behind a node.
+///
+/// As triangles cannot be blurred, the blur is approximated by `SHADOW_LAYERS` concentric
+/// translucent rectangles that grow by `blur` in total.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Shadow {
+    /// The offset of the shadow from the node's rectangle.
+    pub offset: [Scalar; 2],
+    /// The distance over which the edge of the shadow fades out.
+    pub blur: Scalar,
+    /// The color of the shadow at its most opaque.
+    pub color: Color,
+}
+
+/// The handle in the bottom right corner of a resizable node, as returned by `resize_handle`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ResizeHandle {
+    /// The bounding rectangle of the handle relative to the centre of the node.
+    pub rect: Rect,
+    /// The smallest size to which the node may be resized.
+    pub min_size: Dimensions,
+    /// The largest size to which the node may be resized, if any.
+    pub max_size: Option<Dimensions>,
 }
 
 /// Describes the layout of either input or output sockets.
@@ -62,6 +150,15 @@ pub struct SocketLayout {
     pub direction: Direction,
 }
 
+/// The shape used to draw each socket.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SocketShape {
+    /// A rectangle spanning the border with a length of the `socket_length`.
+    Rectangle,
+    /// A disc centred on the socket position with a diameter of the `socket_length`.
+    Circle,
+}
+
 /// Represents the side of a node widget's bounding rectangle.
 ///
 /// This is used to describe default node socket layout.
@@ -81,12 +178,21 @@ widget_ids! {
         //
         // Triangulation order is as follows:
         //
-        // 1. Inner rectangle surface (two triangles).
-        // 2. Border (eight triangles).
-        // 3. Sockets (two triangles per socket).
+        // 1. Drop shadow (two triangles per layer, if any).
+        // 2. Inner rectangle surface (two triangles, or a fan of triangles if rounded).
+        // 3. Border (eight triangles, or two triangles per outline segment if rounded).
+        // 4. Status outlines (eight triangles per outline).
+        // 5. Sockets (two triangles per rectangle socket or a fan of triangles per circle socket).
+        // 6. Resize handle (one triangle, if resizable).
         triangles,
         // The unique identifier for the wrapped widget.
         widget,
+        // The chevron for collapsing and expanding collapsible nodes, drawn above the widget.
+        collapse_chevron,
+        // A text label for each labelled input socket.
+        input_labels[],
+        // A text label for each labelled output socket.
+        output_labels[],
     }
 }
 
@@ -95,10 +201,22 @@ pub struct State {
     ids: Ids,
     // Tracks whether or not a socket is currently captured under the mouse.
     capturing_socket: Option<(SocketType, usize)>,
+    // Whether or not the node has a resize handle.
+    resizable: bool,
+    // Whether or not the node is collapsed, if it is collapsible.
+    collapsed: Option<bool>,
     // The number of input sockets.
     inputs: usize,
     // The number of output sockets.
     outputs: usize,
+    // The labels of the input sockets, retained for `socket_label`.
+    input_labels: Vec<String>,
+    // The labels of the output sockets, retained for `socket_label`.
+    output_labels: Vec<String>,
+    // The tinted color of each input socket, retained for `socket_color`.
+    input_colors: Vec<Color>,
+    // The tinted color of each output socket, retained for `socket_color`.
+    output_colors: Vec<Color>,
 }
 
 /// Describes whether a socket is associated with a node's inputs or outputs.
@@ -109,7 +227,67 @@ pub enum SocketType { Input, Output }
 #[derive(Clone, Debug)]
 pub struct Event<W> {
     /// The event produced by the inner widget `W`.
-    pub widget_event: W,
+    ///
+    /// This is `None` while the node is collapsed or while the zoom is below the node's
+    /// `lod_threshold`, as the inner widget is not instantiated.
+    pub widget_event: Option<W>,
+}
+
+impl ResizeHandle {
+    /// Constrain the given dimensions to the handle's minimum and maximum sizes.
+    pub fn constrain(&self, dim: Dimensions) -> Dimensions {
+        let [min_w, min_h] = self.min_size;
+        let [w, h] = [dim[0].max(min_w), dim[1].max(min_h)];
+        match self.max_size {
+            Some([max_w, max_h]) => [w.min(max_w), h.min(max_h)],
+            None => [w, h],
+        }
+    }
+}
+
+impl SocketLayout {
+    /// A layout of sockets along the given side in the given direction.
+    pub fn new(side: SocketSide, direction: Direction) -> Self {
+        SocketLayout { side, direction }
+    }
+
+    /// Sockets along the left side, ordered from top to bottom.
+    ///
+    /// This is the default layout for input sockets.
+    pub fn inputs_on_left() -> Self {
+        SocketLayout::new(SocketSide::Left, Direction::Backwards)
+    }
+
+    /// Sockets along the right side, ordered from top to bottom.
+    ///
+    /// This is the default layout for output sockets.
+    pub fn outputs_on_right() -> Self {
+        SocketLayout::new(SocketSide::Right, Direction::Backwards)
+    }
+
+    /// Sockets along the top side, ordered from left to right.
+    pub fn inputs_on_top() -> Self {
+        SocketLayout::new(SocketSide::Top, Direction::Forwards)
+    }
+
+    /// Sockets along the bottom side, ordered from left to right.
+    pub fn outputs_on_bottom() -> Self {
+        SocketLayout::new(SocketSide::Bottom, Direction::Forwards)
+    }
+
+    /// The input and output layouts respectively for graphs that flow from left to right.
+    ///
+    /// Inputs are placed on the left side and outputs on the right.
+    pub fn left_to_right() -> (Self, Self) {
+        (SocketLayout::inputs_on_left(), SocketLayout::outputs_on_right())
+    }
+
+    /// The input and output layouts respectively for graphs that flow from top to bottom.
+    ///
+    /// Inputs are placed on the top side and outputs on the bottom.
+    pub fn top_down() -> (Self, Self) {
+        (SocketLayout::inputs_on_top(), SocketLayout::outputs_on_bottom())
+    }
 }
 
 impl<W> Node<W> {
@@ -121,6 +299,19 @@ impl<W> Node<W> {
             widget,
             inputs: 0,
             outputs: 0,
+            aspect_ratio: None,
+            input_labels: Vec::new(),
+            output_labels: Vec::new(),
+            input_socket_colors: Vec::new(),
+            output_socket_colors: Vec::new(),
+            status_outlines: Vec::new(),
+            tint: None,
+            resizable: false,
+            collapsed: None,
+            lod_threshold: None,
+            zoom: 1.0,
+            appear_duration: None,
+            age: None,
         }
     }
 
@@ -136,6 +327,147 @@ impl<W> Node<W> {
         self
     }
 
+    /// Specify a label for each input socket.
+    ///
+    /// Labels are displayed outside the node's border beside their socket. If there are fewer
+    /// labels than sockets, the remaining sockets are left unlabelled.
+    pub fn input_labels(mut self, labels: Vec<String>) -> Self {
+        self.input_labels = labels;
+        self
+    }
+
+    /// Specify a label for each output socket.
+    ///
+    /// Labels are displayed outside the node's border beside their socket. If there are fewer
+    /// labels than sockets, the remaining sockets are left unlabelled.
+    pub fn output_labels(mut self, labels: Vec<String>) -> Self {
+        self.output_labels = labels;
+        self
+    }
+
+    /// Preserve the given aspect ratio (width / height) of the wrapped widget.
+    ///
+    /// When `Some`, the wrapped widget is sized to the largest rectangle of the given ratio that
+    /// fits within the node's border and is centered within it, leaving the remaining space
+    /// filled with the node's `color`. When `None` (the default), the wrapped widget fills the
+    /// node's inner rectangle.
+    pub fn preserve_aspect(mut self, aspect_ratio: Option<Scalar>) -> Self {
+        self.aspect_ratio = aspect_ratio;
+        self
+    }
+
+    /// Tint the node by multiplying its fill, border and socket colors by the given color.
+    ///
+    /// This allows for categorising nodes by color without restyling each of them.
+    pub fn tint(mut self, color: Color) -> Self {
+        self.tint = Some(color);
+        self
+    }
+
+    /// Apply the node colors from the given `GraphColors`.
+    ///
+    /// This sets the color of the node's inner rectangle, border and sockets.
+    pub fn graph_colors(mut self, colors: GraphColors) -> Self {
+        self.style.color = Some(colors.node);
+        self.style.border_color = Some(colors.node_border);
+        self.style.socket_color = Some(colors.socket);
+        self
+    }
+
+    /// Whether or not to draw a handle in the node's bottom right corner with which it may be
+    /// resized.
+    ///
+    /// Dragging the handle emits a `NodeEvent::Resized` from the **Graph** rather than moving the
+    /// node. See `NodeEvent::Resized` for storing and applying the requested size.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Make the node collapsible, collapsing it if `collapsed` is `true`.
+    ///
+    /// Collapsible nodes have a chevron in their top right corner. Clicking the chevron emits a
+    /// `NodeEvent::CollapseToggled` from the **Graph**, so the collapsed state of each node should
+    /// be stored by the user and passed to this method upon instantiation.
+    ///
+    /// A collapsed node is drawn as a title bar of `COLLAPSED_HEIGHT` along the top of the node's
+    /// rectangle. Its inner widget is not instantiated, while its border and sockets are drawn
+    /// and its sockets are laid out over the title bar. The node's bounding rectangle itself is
+    /// unchanged, so the area beneath the title bar still receives the node's input.
+    pub fn collapsed(mut self, collapsed: bool) -> Self {
+        self.collapsed = Some(collapsed);
+        self
+    }
+
+    /// Only draw the node's fill, border and sockets while the `zoom` is below the given
+    /// threshold.
+    ///
+    /// When zoomed far out, the inner widget and socket labels are mostly illegible clutter, so
+    /// they are not instantiated and the node is drawn as a simple filled rectangle instead. The
+    /// inner widget produces no event while it is skipped.
+    pub fn lod_threshold(mut self, threshold: Scalar) -> Self {
+        self.lod_threshold = Some(threshold);
+        self
+    }
+
+    /// Specify the zoom of the camera through which the node is viewed.
+    ///
+    /// This is compared against the `lod_threshold`. Nodes produced by
+    /// `NodeContext::styled_widget` are given the zoom of the **Graph**'s camera. Defaults to
+    /// `1.0`.
+    pub fn zoom(mut self, zoom: Scalar) -> Self {
+        self.zoom = zoom;
+        self
+    }
+
+    /// Fade the node in over the given number of updates after it first appears.
+    ///
+    /// The opacity of the node's fill, border and sockets ramps from `0.0` to `1.0` according to
+    /// the node's `age`. The inner widget is drawn as usual. Nodes without an age (e.g. those
+    /// present since the graph's first update) are drawn at full opacity.
+    pub fn appear_duration(mut self, updates: u64) -> Self {
+        self.appear_duration = Some(updates);
+        self
+    }
+
+    /// Specify the number of updates since the node first appeared.
+    ///
+    /// Nodes produced by `NodeContext::styled_widget` are given their `NodeContext::age`.
+    pub fn age(mut self, updates: u64) -> Self {
+        self.age = Some(updates);
+        self
+    }
+
+    /// The smallest size to which the node may be resized via its resize handle.
+    pub fn min_size(mut self, size: Dimensions) -> Self {
+        self.style.min_size = Some(size);
+        self
+    }
+
+    /// The largest size to which the node may be resized via its resize handle.
+    ///
+    /// By default, there is no maximum size.
+    pub fn max_size(mut self, size: Dimensions) -> Self {
+        self.style.max_size = Some(Some(size));
+        self
+    }
+
+    /// Draw the given drop shadow behind the node.
+    ///
+    /// By default, nodes have no shadow.
+    pub fn shadow(mut self, shadow: Shadow) -> Self {
+        self.style.shadow = Some(Some(shadow));
+        self
+    }
+
+    /// Round the corners of the node's border and inner rectangle with the given radius.
+    ///
+    /// The radius is limited to half of the node's shortest side.
+    pub fn corner_radius(mut self, radius: Scalar) -> Self {
+        self.style.corner_radius = Some(Some(radius));
+        self
+    }
+
     /// Specify the color for the node's inner rectangle.
     pub fn color(mut self, color: Color) -> Self {
         self.style.color = Some(color);
@@ -163,6 +495,50 @@ impl<W> Node<W> {
         self
     }
 
+    /// Specify a color for each input socket.
+    ///
+    /// Sockets without a color at their index use the `socket_color`.
+    pub fn input_socket_colors(mut self, colors: Vec<Color>) -> Self {
+        self.input_socket_colors = colors;
+        self
+    }
+
+    /// Specify a color for each output socket.
+    ///
+    /// Sockets without a color at their index use the `socket_color`.
+    pub fn output_socket_colors(mut self, colors: Vec<Color>) -> Self {
+        self.output_socket_colors = colors;
+        self
+    }
+
+    /// Draw concentric outlines of the given thickness and color around the node.
+    ///
+    /// Outlines are ordered from the innermost (surrounding the node's border) to the outermost.
+    /// This is useful for indicating multiple states at once, e.g. an error ring within a
+    /// selection ring.
+    pub fn status_outlines(mut self, outlines: &[(Scalar, Color)]) -> Self {
+        self.status_outlines = outlines.to_vec();
+        self
+    }
+
+    /// Specify the color for the socket labels.
+    pub fn socket_label_color(mut self, color: Color) -> Self {
+        self.style.socket_label_color = Some(color);
+        self
+    }
+
+    /// Specify the font size for the socket labels.
+    pub fn socket_label_font_size(mut self, size: FontSize) -> Self {
+        self.style.socket_label_font_size = Some(size);
+        self
+    }
+
+    /// Specify the shape used to draw the node's sockets.
+    pub fn socket_shape(mut self, shape: SocketShape) -> Self {
+        self.style.socket_shape = Some(shape);
+        self
+    }
+
     /// Specify the layout of the input sockets.
     pub fn input_socket_layout(mut self, layout: SocketLayout) -> Self {
         self.style.input_socket_layout = Some(layout);
@@ -177,7 +553,7 @@ impl<W> Node<W> {
 }
 
 impl<W> Deref for Event<W> {
-    type Target = W;
+    type Target = Option<W>;
     fn deref(&self) -> &Self::Target {
         &self.widget_event
     }
@@ -244,9 +620,11 @@ fn socket_step_and_start(
     let direction_scalar = direction_scalar(direction);
     let socket_range = rect_range(axis, inner_rect);
     let socket_position_range = socket_range.pad(socket_length / 2.0);
-    let socket_start_scalar = match direction {
-        Direction::Forwards => socket_position_range.start,
-        Direction::Backwards => socket_position_range.end,
+    // A single socket is placed at the centre of the side.
+    let socket_start_scalar = match (n_sockets, direction) {
+        (1, _) => socket_position_range.middle(),
+        (_, Direction::Forwards) => socket_position_range.start,
+        (_, Direction::Backwards) => socket_position_range.end,
     };
     let step = if n_sockets > 1 {
         socket_position_range.len() * direction_scalar / (n_sockets - 1) as Scalar
@@ -270,6 +648,147 @@ fn socket_step_and_start(
     (step, socket_start_position)
 }
 
+// The largest dimensions of the given aspect ratio (width / height) that fit within `dim`.
+fn fit_aspect_ratio(dim: [Scalar; 2], ratio: Scalar) -> [Scalar; 2] {
+    let [w, h] = dim;
+    if w > h * ratio {
+        [h * ratio, h]
+    } else {
+        [w, w / ratio]
+    }
+}
+
+/// The position of the centre of the socket at the given index.
+///
+/// This is the same position at which the `Node` widget renders the socket, so it may be used
+/// to route edges (or anything else) to exactly meet the rendered sockets.
+pub fn socket_point(
+    node_rect: Rect,
+    border: Scalar,
+    socket_length: Scalar,
+    index: usize,
+    n_sockets: usize,
+    layout: SocketLayout,
+) -> Point {
+    let SocketLayout { side, direction } = layout;
+    let (axis, side_scalar) = side_axis_and_scalar(node_rect, side, border);
+    let inner_rect = node_rect.pad(border);
+    let (step, start_pos) = socket_step_and_start(n_sockets, axis, direction, inner_rect,
+                                                  socket_length, side_scalar);
+    socket_position(index, start_pos, step)
+}
+
+// The rectangle within which the node is drawn, shrunk to a title bar along the top of the
+// node's rectangle if collapsed.
+fn visible_rect(rect: Rect, collapsed: bool, border: Scalar) -> Rect {
+    match collapsed {
+        false => rect,
+        true => {
+            let h = (COLLAPSED_HEIGHT + border * 2.0).min(rect.h());
+            Rect::from_xy_dim([rect.x(), rect.top() - h / 2.0], [rect.w(), h])
+        },
+    }
+}
+
+// The rectangle of the collapse chevron in the top right corner within the border of the given
+// visible node rectangle.
+fn collapse_chevron_rect(rect: Rect, border: Scalar) -> Rect {
+    let pad = (COLLAPSED_HEIGHT - COLLAPSE_CHEVRON_SIZE) / 2.0;
+    let x = rect.right() - border - pad - COLLAPSE_CHEVRON_SIZE / 2.0;
+    let y = rect.top() - border - pad - COLLAPSE_CHEVRON_SIZE / 2.0;
+    Rect::from_xy_dim([x, y], [COLLAPSE_CHEVRON_SIZE; 2])
+}
+
+// The color of each of the `n_sockets` sockets, falling back to the `default` color for sockets
+// beyond the end of `colors` and multiplied by the tint, if any.
+fn resolve_socket_colors(
+    n_sockets: usize,
+    colors: &[Color],
+    default: Color,
+    tint: Option<Color>,
+) -> Vec<Color>
+{
+    (0..n_sockets)
+        .map(|i| tint_color(colors.get(i).cloned().unwrap_or(default), tint))
+        .collect()
+}
+
+// The triangles for the socket within each of the given rectangles, each colored by the color
+// at the same index within `colors`.
+fn colored_socket_triangles<I>(
+    rects: I,
+    colors: &[Color],
+    shape: SocketShape,
+    socket_length: Scalar,
+) -> Vec<Triangle<ColoredPoint>>
+where
+    I: IntoIterator<Item=Rect>,
+{
+    rects.into_iter()
+        .zip(colors)
+        .flat_map(|(rect, &color)| {
+            let triangles = match shape {
+                SocketShape::Rectangle => {
+                    let (a, b) = widget::primitive::shape::rectangle::triangles(rect);
+                    vec![a, b]
+                },
+                SocketShape::Circle => circle_triangles(rect.xy(), socket_length / 2.0),
+            };
+            let rgba: color::Rgba = color.into();
+            triangles.into_iter().map(move |Triangle(p)| {
+                Triangle([(p[0], rgba), (p[1], rgba), (p[2], rgba)])
+            })
+        })
+        .collect()
+}
+
+// Multiply the given color by the tint, if any.
+fn tint_color(color: Color, tint: Option<Color>) -> Color {
+    match tint {
+        None => color,
+        Some(tint) => {
+            let color::Rgba(r, g, b, a) = color.to_rgb();
+            let color::Rgba(tr, tg, tb, ta) = tint.to_rgb();
+            color::rgba(r * tr, g * tg, b * tb, a * ta)
+        },
+    }
+}
+
+// The points around the outline of the given rectangle with corners of the given radius.
+//
+// Points are ordered counter-clockwise beginning at the start of the top right corner. Each corner
+// is described by `CORNER_RESOLUTION + 1` points, so that the outlines of two rectangles always
+// contain the same number of points.
+fn rounded_rect_outline(rect: Rect, radius: Scalar) -> Vec<Point> {
+    use std::f64::consts::PI;
+    let (l, r, b, t) = rect.l_r_b_t();
+    let corners = [
+        ([r - radius, t - radius], 0.0),
+        ([l + radius, t - radius], PI / 2.0),
+        ([l + radius, b + radius], PI),
+        ([r - radius, b + radius], PI * 1.5),
+    ];
+    corners.iter()
+        .flat_map(|&(centre, start)| {
+            (0..CORNER_RESOLUTION + 1).map(move |i| {
+                let angle = start + (PI / 2.0) * i as Scalar / CORNER_RESOLUTION as Scalar;
+                [centre[0] + radius * angle.cos(), centre[1] + radius * angle.sin()]
+            })
+        })
+        .collect()
+}
+
+// Triangles approximating a disc with the given centre and radius.
+fn circle_triangles(centre: Point, radius: Scalar) -> Vec<Triangle<Point>> {
+    let point = |i: usize| {
+        let angle = 2.0 * ::std::f64::consts::PI * i as Scalar / SOCKET_CIRCLE_RESOLUTION as Scalar;
+        [centre[0] + radius * angle.cos(), centre[1] + radius * angle.sin()]
+    };
+    (0..SOCKET_CIRCLE_RESOLUTION)
+        .map(|i| Triangle([centre, point(i), point(i + 1)]))
+        .collect()
+}
+
 // Produce the `Rect` for a socket from the raw params required.
 fn socket_rectangle(
     index: usize,
@@ -279,12 +798,11 @@ fn socket_rectangle(
     layout: SocketLayout,
     socket_length: Scalar,
 ) -> Rect {
-    let SocketLayout { side, direction } = layout;
-    let (axis, side_scalar) = side_axis_and_scalar(node_rect, side, border);
-    let inner_rect = node_rect.pad(border);
-    let (step, start_pos) = socket_step_and_start(n_sockets, axis, direction, inner_rect,
-                                                  socket_length, side_scalar);
-    let xy = socket_position(index, start_pos, step);
+    let axis = match layout.side {
+        SocketSide::Left | SocketSide::Right => Axis::Y,
+        SocketSide::Top | SocketSide::Bottom => Axis::X,
+    };
+    let xy = socket_point(node_rect, border, socket_length, index, n_sockets, layout);
     let socket_dim = socket_rect_dim(axis, border, socket_length);
     let rect = Rect::from_xy_dim(xy, socket_dim);
     rect
@@ -308,8 +826,8 @@ pub fn socket_rect(
                 None => return None,
                 Some(unique) => unique,
             };
-            let rect = container.rect;
             let border = style.border(&ui.theme);
+            let rect = visible_rect(container.rect, state.collapsed == Some(true), border);
             let socket_length = style.socket_length(&ui.theme);
 
             let (n_sockets, layout) = match socket_type {
@@ -338,6 +856,61 @@ where
     (start_rect, end_rect)
 }
 
+/// Returns the `SocketLayout`s for the inputs and outputs of the given node respectively.
+///
+/// Returns `None` if no node is found for the given `widget::Id`.
+pub fn socket_layouts(node_id: widget::Id, ui: &Ui) -> Option<(SocketLayout, SocketLayout)> {
+    ui.widget_graph()
+        .widget(node_id)
+        .and_then(|container| container.state_and_style::<State, Style>())
+        .map(|unique| {
+            let input = unique.style.input_socket_layout(&ui.theme);
+            let output = unique.style.output_socket_layout(&ui.theme);
+            (input, output)
+        })
+}
+
+/// Returns the `SocketSide` of an edge's start (output) and end (input) sockets.
+pub fn edge_socket_sides<NI>(edge: &super::Edge<NI>, ui: &Ui) -> (SocketSide, SocketSide)
+where
+    NI: super::NodeId,
+{
+    let (start_id, end_id) = super::edge_node_widget_ids(edge, ui);
+    let (_, start_layout) = socket_layouts(start_id, ui)
+        .expect("no node widget found for the edge's `start_id`");
+    let (end_layout, _) = socket_layouts(end_id, ui)
+        .expect("no node widget found for the edge's `end_id`");
+    (start_layout.side, end_layout.side)
+}
+
+/// Returns the position of an edge's start and end sockets.
+///
+/// These are the centres of the rectangles returned by `edge_socket_rects`.
+pub fn edge_socket_points<NI>(edge: &super::Edge<NI>, ui: &Ui) -> (Point, Point)
+where
+    NI: super::NodeId,
+{
+    let (start_rect, end_rect) = edge_socket_rects(edge, ui);
+    (start_rect.xy(), end_rect.xy())
+}
+
+/// The rectangle within which the given node is drawn.
+///
+/// This is the node widget's rectangle, or the title bar along its top if the node is collapsed.
+/// Sockets are laid out along the sides of this rectangle.
+///
+/// Returns `None` if no node is found for the given `widget::Id`.
+pub fn node_rect(node_id: widget::Id, ui: &Ui) -> Option<Rect> {
+    ui.widget_graph()
+        .widget(node_id)
+        .and_then(|container| {
+            container.state_and_style::<State, Style>().map(|unique| {
+                let border = unique.style.border(&ui.theme);
+                visible_rect(container.rect, unique.state.collapsed == Some(true), border)
+            })
+        })
+}
+
 /// Produces an iterator yielding a `Rect` for each socket for both inputs and outputs
 /// respectively.
 ///
@@ -351,8 +924,8 @@ pub fn socket_rects(node_id: widget::Id, ui: &Ui) -> Option<(SocketRects, Socket
                 None => return None,
                 Some(unique) => unique,
             };
-            let rect = container.rect;
             let border = style.border(&ui.theme);
+            let rect = visible_rect(container.rect, state.collapsed == Some(true), border);
             let socket_length = style.socket_length(&ui.theme);
             let input_socket_rects = SocketRects {
                 index: 0,
@@ -374,6 +947,129 @@ pub fn socket_rects(node_id: widget::Id, ui: &Ui) -> Option<(SocketRects, Socket
         })
 }
 
+/// The resize handle of the given node, if it is resizable.
+///
+/// Returns `None` if no node is found for the given `widget::Id` or if the node is not resizable.
+pub fn resize_handle(node_id: widget::Id, ui: &Ui) -> Option<ResizeHandle> {
+    ui.widget_graph()
+        .widget(node_id)
+        .and_then(|container| {
+            let unique = match container.state_and_style::<State, Style>() {
+                Some(unique) if unique.state.resizable => unique,
+                _ => return None,
+            };
+            let border = unique.style.border(&ui.theme);
+            let rect = visible_rect(container.rect, unique.state.collapsed == Some(true), border);
+            let half = RESIZE_HANDLE_SIZE / 2.0;
+            let xy = [rect.right() - half, rect.bottom() + half];
+            let handle_rect = Rect::from_xy_dim(xy, [RESIZE_HANDLE_SIZE; 2])
+                .shift([-container.rect.x(), -container.rect.y()]);
+            let min_size = unique.style.min_size(&ui.theme);
+            let max_size = unique.style.max_size(&ui.theme);
+            Some(ResizeHandle { rect: handle_rect, min_size, max_size })
+        })
+}
+
+/// The collapse chevron of the given node relative to its centre along with whether or not the
+/// node is currently collapsed.
+///
+/// Returns `None` if no node is found for the given `widget::Id` or if the node is not
+/// collapsible.
+pub fn collapse_chevron(node_id: widget::Id, ui: &Ui) -> Option<(Rect, bool)> {
+    ui.widget_graph()
+        .widget(node_id)
+        .and_then(|container| {
+            let unique = container.state_and_style::<State, Style>();
+            let &conrod::graph::UniqueWidgetState { ref state, ref style } = match unique {
+                None => return None,
+                Some(unique) => unique,
+            };
+            let collapsed = match state.collapsed {
+                None => return None,
+                Some(collapsed) => collapsed,
+            };
+            let border = style.border(&ui.theme);
+            let rect = visible_rect(container.rect, collapsed, border);
+            let chevron_rect = collapse_chevron_rect(rect, border)
+                .shift([-container.rect.x(), -container.rect.y()]);
+            Some((chevron_rect, collapsed))
+        })
+}
+
+/// The label of the socket of the given type at the given index on the given node, if it has one.
+///
+/// Returns `None` if no node is found for the given `widget::Id` or if the socket is unlabelled.
+pub fn socket_label(
+    node_id: widget::Id,
+    socket_type: SocketType,
+    socket_index: usize,
+    ui: &Ui,
+) -> Option<&str> {
+    ui.widget_graph()
+        .widget(node_id)
+        .and_then(|container| container.state_and_style::<State, Style>())
+        .and_then(|unique| {
+            let labels = match socket_type {
+                SocketType::Input => &unique.state.input_labels,
+                SocketType::Output => &unique.state.output_labels,
+            };
+            labels.get(socket_index).map(|label| &label[..])
+        })
+}
+
+/// The color of the socket of the given type at the given index on the given node, including the
+/// node's tint.
+///
+/// Returns `None` if no node is found for the given `widget::Id` or if the node has no such
+/// socket.
+pub fn socket_color(
+    node_id: widget::Id,
+    socket_type: SocketType,
+    socket_index: usize,
+    ui: &Ui,
+) -> Option<Color> {
+    ui.widget_graph()
+        .widget(node_id)
+        .and_then(|container| container.state_and_style::<State, Style>())
+        .and_then(|unique| {
+            let colors = match socket_type {
+                SocketType::Input => &unique.state.input_colors,
+                SocketType::Output => &unique.state.output_colors,
+            };
+            colors.get(socket_index).cloned()
+        })
+}
+
+/// Find all pairs of sockets on the given node whose rectangles overlap.
+///
+/// Clicks over an overlapping region are ambiguous, which usually indicates that the node is too
+/// small for its number of sockets. Each socket is described by its type and index.
+///
+/// Returns `None` if no node is found for the given `widget::Id`.
+pub fn socket_overlaps(
+    node_id: widget::Id,
+    ui: &Ui,
+) -> Option<Vec<((SocketType, usize), (SocketType, usize))>> {
+    socket_rects(node_id, ui).map(|(inputs, outputs)| {
+        let sockets: Vec<_> = inputs.enumerate()
+            .map(|(i, rect)| ((SocketType::Input, i), rect))
+            .chain(outputs.enumerate().map(|(i, rect)| ((SocketType::Output, i), rect)))
+            .collect();
+        let mut overlaps = vec![];
+        for (i, &(a, a_rect)) in sockets.iter().enumerate() {
+            for &(b, b_rect) in &sockets[i + 1..] {
+                let overlapping = a_rect.overlap(b_rect)
+                    .map(|overlap| overlap.w() > 0.0 && overlap.h() > 0.0)
+                    .unwrap_or(false);
+                if overlapping {
+                    overlaps.push((a, b));
+                }
+            }
+        }
+        overlaps
+    })
+}
+
 /// The rectangle for each socket (either inputs or outputs only).
 #[derive(Clone)]
 pub struct SocketRects {
@@ -423,8 +1119,14 @@ where
         State {
             ids: Ids::new(id_gen),
             capturing_socket: None,
+            resizable: self.resizable,
+            collapsed: self.collapsed,
             inputs: self.inputs,
             outputs: self.outputs,
+            input_labels: self.input_labels.clone(),
+            output_labels: self.output_labels.clone(),
+            input_colors: Vec::new(),
+            output_colors: Vec::new(),
         }
     }
 
@@ -434,10 +1136,45 @@ where
 
     fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
         let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
-        let Node { widget, inputs, outputs, .. } = self;
+        let Node {
+            widget,
+            inputs,
+            outputs,
+            aspect_ratio,
+            input_labels,
+            output_labels,
+            input_socket_colors,
+            output_socket_colors,
+            status_outlines,
+            tint,
+            resizable,
+            collapsed,
+            lod_threshold,
+            zoom,
+            appear_duration,
+            age,
+            ..
+        } = self;
         let socket_length = style.socket_length(&ui.theme);
         let border = style.border(&ui.theme);
 
+        // Collapsed nodes are drawn within a title bar along the top of their rectangle.
+        let is_collapsed = collapsed == Some(true);
+        let rect = visible_rect(rect, is_collapsed, border);
+
+        // Zoomed far out, only the node's fill, border and sockets are drawn.
+        let is_simplified = lod_threshold.map(|threshold| zoom < threshold).unwrap_or(false);
+
+        // Fade newly appeared nodes in by reducing the alpha of the tint.
+        let tint = match (appear_duration, age) {
+            (Some(duration), Some(age)) if age < duration => {
+                let opacity = age as f32 / duration as f32;
+                let color::Rgba(r, g, b, a) = tint.unwrap_or(color::WHITE).to_rgb();
+                Some(color::rgba(r, g, b, a * opacity))
+            },
+            _ => tint,
+        };
+
         if state.inputs != inputs {
             state.update(|state| state.inputs = inputs);
         }
@@ -446,6 +1183,35 @@ where
             state.update(|state| state.outputs = outputs);
         }
 
+        if state.resizable != resizable {
+            state.update(|state| state.resizable = resizable);
+        }
+
+        if state.collapsed != collapsed {
+            state.update(|state| state.collapsed = collapsed);
+        }
+
+        if state.input_labels != input_labels {
+            state.update(|state| state.input_labels = input_labels.clone());
+        }
+
+        if state.output_labels != output_labels {
+            state.update(|state| state.output_labels = output_labels.clone());
+        }
+
+        // Resolve the color of each socket so that edges may match them, e.g. via
+        // `Edge::gradient_line`.
+        let socket_color = style.socket_color(&ui.theme);
+        let input_colors = resolve_socket_colors(inputs, &input_socket_colors, socket_color, tint);
+        if state.input_colors != input_colors {
+            state.update(|state| state.input_colors = input_colors.clone());
+        }
+        let output_colors = resolve_socket_colors(outputs, &output_socket_colors, socket_color,
+                                                  tint);
+        if state.output_colors != output_colors {
+            state.update(|state| state.output_colors = output_colors.clone());
+        }
+
         let input_socket_layout = style.input_socket_layout(&ui.theme);
         let output_socket_layout = style.output_socket_layout(&ui.theme);
 
@@ -502,18 +1268,58 @@ where
             Triangle([(arr[0], color), (arr[1], color), (arr[2], color)])
         }
 
-        // The triangles for the inner rectangle surface first.
+        // The radius of the outer and inner corners if the node is rounded.
+        let corner_radius = style.corner_radius(&ui.theme).map(|radius| {
+            let outer = radius.max(0.0).min(rect.w().min(rect.h()) / 2.0);
+            let inner = (outer - border).max(0.0);
+            (outer, inner)
+        });
+
+        // The triangles for the drop shadow beneath everything else, largest layer first.
+        let shadow_triangles: Vec<_> = match style.shadow(&ui.theme) {
+            None => vec![],
+            Some(Shadow { offset, blur, color }) => {
+                let color::Rgba(r, g, b, a) = color.to_rgb();
+                let layer_rgba = color::Rgba(r, g, b, a / SHADOW_LAYERS as f32);
+                let shadow_rect = rect.shift(offset);
+                (0..SHADOW_LAYERS)
+                    .rev()
+                    .flat_map(|i| {
+                        let grow = blur * (i + 1) as Scalar / SHADOW_LAYERS as Scalar;
+                        let layer_rect = shadow_rect.pad(-grow / 2.0);
+                        let (a, b) = widget::primitive::shape::rectangle::triangles(layer_rect);
+                        once(a).chain(once(b))
+                    })
+                    .map(|tri| color_triangle(tri, layer_rgba))
+                    .collect()
+            },
+        };
+
+        // The triangles for the inner rectangle surface.
         let inner_rect = rect.pad(border);
-        let (inner_tri_a, inner_tri_b) = widget::primitive::shape::rectangle::triangles(inner_rect);
-        let inner_color = style.color(&ui.theme).into();
-        let inner_triangles = once(inner_tri_a)
-            .chain(once(inner_tri_b))
+        let inner_color = tint_color(style.color(&ui.theme), tint).into();
+        let inner_triangles: Vec<_> = match corner_radius {
+            None => {
+                let (a, b) = widget::primitive::shape::rectangle::triangles(inner_rect);
+                vec![a, b]
+            },
+            Some((_, inner_radius)) => {
+                let centre = inner_rect.xy();
+                let outline = rounded_rect_outline(inner_rect, inner_radius);
+                outline.iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .map(|(&a, &b)| Triangle([centre, a, b]))
+                    .collect()
+            },
+        };
+        let inner_triangles = inner_triangles
+            .into_iter()
             .map(|tri| color_triangle(tri, inner_color));
 
         // Triangles for the border.
         //
         // Color the border based on interaction.
-        let border_color = style.border_color(&ui.theme);
+        let border_color = tint_color(style.border_color(&ui.theme), tint);
         let border_color = match maybe_socket_interaction.is_some() {
             true => border_color,
             false => {
@@ -530,56 +1336,252 @@ where
             },
         };
 
-        let border_triangles = widget::bordered_rectangle::border_triangles(rect, border).unwrap();
+        let border_triangles: Vec<_> = match corner_radius {
+            None => widget::bordered_rectangle::border_triangles(rect, border).unwrap().to_vec(),
+            Some((outer_radius, inner_radius)) => {
+                let outer = rounded_rect_outline(rect, outer_radius);
+                let inner = rounded_rect_outline(inner_rect, inner_radius);
+                let n = outer.len();
+                (0..n)
+                    .flat_map(|i| {
+                        let j = (i + 1) % n;
+                        let a = Triangle([outer[i], outer[j], inner[j]]);
+                        let b = Triangle([outer[i], inner[j], inner[i]]);
+                        once(a).chain(once(b))
+                    })
+                    .collect()
+            },
+        };
         let border_rgba = border_color.into();
         let border_triangles = border_triangles
-            .iter()
-            .cloned()
+            .into_iter()
             .map(|tri| color_triangle(tri, border_rgba));
 
+        // Triangles for the status outlines, each surrounding the last.
+        let mut outline_rect = rect;
+        let mut outline_triangles = vec![];
+        for &(thickness, color) in &status_outlines {
+            if thickness <= 0.0 {
+                continue;
+            }
+            outline_rect = outline_rect.pad(-thickness);
+            let rgba = color.into();
+            let triangles = widget::bordered_rectangle::border_triangles(outline_rect, thickness);
+            if let Some(triangles) = triangles {
+                outline_triangles.extend(triangles.iter().map(|&tri| color_triangle(tri, rgba)));
+            }
+        }
+
         // A function for producing the triangles for sockets along some axis.
-        let socket_color = style.socket_color(&ui.theme);
-        let socket_triangles = |socket_type, n_sockets, layout| {
-            socket_rectangles(n_sockets, layout)
-                .enumerate()
-                .flat_map(move |(i, rect)| {
-                    let (tri_a, tri_b) = widget::primitive::shape::rectangle::triangles(rect);
-                    let color = match maybe_socket_interaction {
-                        Some((ty, ix, action)) if ty == socket_type && ix == i => match action {
-                            Interaction::Hover => socket_color.highlighted(),
-                            Interaction::Press => socket_color.clicked(),
-                        },
-                        _ => socket_color,
+        let socket_shape = style.socket_shape(&ui.theme);
+        let socket_triangles = |socket_type, n_sockets, layout, mut colors: Vec<Color>| {
+            if let Some((ty, ix, ref action)) = maybe_socket_interaction {
+                if let (true, Some(color)) = (ty == socket_type, colors.get_mut(ix)) {
+                    *color = match *action {
+                        Interaction::Hover => color.highlighted(),
+                        Interaction::Press => color.clicked(),
                     };
-                    let rgba = color.into();
-                    let a = color_triangle(tri_a, rgba);
-                    let b = color_triangle(tri_b, rgba);
-                    once(a).chain(once(b))
-                })
+                }
+            }
+            colored_socket_triangles(socket_rectangles(n_sockets, layout), &colors, socket_shape,
+                                     socket_length)
         };
 
         // Triangles for sockets.
-        let input_socket_triangles = socket_triangles(SocketType::Input, inputs, input_socket_layout);
-        let output_socket_triangles = socket_triangles(SocketType::Output, outputs, output_socket_layout);
+        let input_socket_triangles = socket_triangles(SocketType::Input, inputs, input_socket_layout,
+                                                      input_colors);
+        let output_socket_triangles = socket_triangles(SocketType::Output, outputs,
+                                                       output_socket_layout, output_colors);
+
+        // The triangle for the resize handle in the bottom right corner.
+        let resize_handle_triangle = match resizable {
+            false => None,
+            true => {
+                let (r, b) = (rect.right(), rect.bottom());
+                let points = [[r, b], [r, b + RESIZE_HANDLE_SIZE], [r - RESIZE_HANDLE_SIZE, b]];
+                let rgba = border_color.highlighted().into();
+                Some(color_triangle(Triangle(points), rgba))
+            },
+        };
 
         // Submit the triangles for the graphical elements of the widget.
-        let triangles = inner_triangles
+        let triangles = shadow_triangles
+            .into_iter()
+            .chain(inner_triangles)
             .chain(border_triangles)
+            .chain(outline_triangles)
             .chain(input_socket_triangles)
-            .chain(output_socket_triangles);
+            .chain(output_socket_triangles)
+            .chain(resize_handle_triangle);
         widget::Triangles::multi_color(triangles)
             .with_bounding_rect(rect)
             .graphics_for(id)
             .parent(id)
             .set(state.ids.triangles, ui);
 
-        // Instantiate the widget.
-        let widget_event = widget
-            .wh(inner_rect.dim())
-            .xy(inner_rect.xy())
-            .parent(id)
-            .set(state.ids.widget, ui);
+        // Socket labels are skipped while the node is simplified.
+        let (input_labels, output_labels) = match is_simplified {
+            true => (Vec::new(), Vec::new()),
+            false => (input_labels, output_labels),
+        };
+
+        // Ensure there is a `widget::Id` for each socket label.
+        let n_input_labels = ::std::cmp::min(input_labels.len(), inputs);
+        let n_output_labels = ::std::cmp::min(output_labels.len(), outputs);
+        if state.ids.input_labels.len() < n_input_labels
+        || state.ids.output_labels.len() < n_output_labels {
+            let id_gen = &mut ui.widget_id_generator();
+            state.update(|state| {
+                if state.ids.input_labels.len() < n_input_labels {
+                    state.ids.input_labels.resize(n_input_labels, id_gen);
+                }
+                if state.ids.output_labels.len() < n_output_labels {
+                    state.ids.output_labels.resize(n_output_labels, id_gen);
+                }
+            });
+        }
+
+        // Instantiate the socket labels outside the border beside their sockets.
+        //
+        // Labels are positioned relative to the triangles which share the bounds of the node.
+        let label_color = style.socket_label_color(&ui.theme);
+        let label_font_size = style.socket_label_font_size(&ui.theme);
+        let label_pad = socket_length / 2.0;
+        let labelled_sockets = socket_rectangles(inputs, input_socket_layout)
+            .zip(input_labels.iter().zip(state.ids.input_labels.iter()))
+            .map(|(rect, label)| (input_socket_layout.side, rect, label))
+            .chain({
+                socket_rectangles(outputs, output_socket_layout)
+                    .zip(output_labels.iter().zip(state.ids.output_labels.iter()))
+                    .map(|(rect, label)| (output_socket_layout.side, rect, label))
+            });
+        for (side, socket_rect, (label, &label_id)) in labelled_sockets {
+            let text = widget::Text::new(label)
+                .font_size(label_font_size)
+                .color(label_color);
+            let text = match side {
+                SocketSide::Left => text.left_from(state.ids.triangles, label_pad).y(socket_rect.y()),
+                SocketSide::Right => text.right_from(state.ids.triangles, label_pad).y(socket_rect.y()),
+                SocketSide::Top => text.up_from(state.ids.triangles, label_pad).x(socket_rect.x()),
+                SocketSide::Bottom => text.down_from(state.ids.triangles, label_pad).x(socket_rect.x()),
+            };
+            text.graphics_for(id)
+                .parent(id)
+                .set(label_id, ui);
+        }
+
+        // Instantiate the widget, fitting it to the aspect ratio if one was given.
+        //
+        // The widget is skipped while the node is collapsed or simplified.
+        let widget_event = match is_collapsed || is_simplified {
+            true => None,
+            false => {
+                let widget_dim = match aspect_ratio {
+                    Some(ratio) if ratio > 0.0 => fit_aspect_ratio(inner_rect.dim(), ratio),
+                    _ => inner_rect.dim(),
+                };
+                let widget_event = widget
+                    .wh(widget_dim)
+                    .xy(inner_rect.xy())
+                    .parent(id)
+                    .set(state.ids.widget, ui);
+                Some(widget_event)
+            },
+        };
+
+        // Draw the chevron above the widget, pointing right while collapsed and down otherwise.
+        if let Some(collapsed) = collapsed {
+            let [x, y] = collapse_chevron_rect(rect, border).xy();
+            let (half, quarter) = (COLLAPSE_CHEVRON_SIZE / 2.0, COLLAPSE_CHEVRON_SIZE / 4.0);
+            let points = match collapsed {
+                true => [[x - quarter, y + half], [x - quarter, y - half], [x + quarter, y]],
+                false => [[x - half, y + quarter], [x + half, y + quarter], [x, y - quarter]],
+            };
+            let chevron_color = style.socket_label_color(&ui.theme);
+            widget::Triangles::single_color(chevron_color, Some(Triangle(points)))
+                .with_bounding_rect(collapse_chevron_rect(rect, border))
+                .graphics_for(id)
+                .parent(id)
+                .set(state.ids.collapse_chevron, ui);
+        }
 
         Event { widget_event }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // For a 100x60 node at the origin with a border of 6 and sockets of length 10, the position of
+    // the socket along the side at `offset` from the centre of the side.
+    fn expected_socket_point(side: SocketSide, offset: Scalar) -> Point {
+        match side {
+            SocketSide::Left => [-47.0, offset],
+            SocketSide::Right => [47.0, offset],
+            SocketSide::Bottom => [offset, -27.0],
+            SocketSide::Top => [offset, 27.0],
+        }
+    }
+
+    #[test]
+    fn socket_points_for_zero_one_and_two_sockets() {
+        let node_rect = Rect::from_xy_dim([0.0, 0.0], [100.0, 60.0]);
+        let (border, socket_length) = (6.0, 10.0);
+        let sides = [SocketSide::Left, SocketSide::Right, SocketSide::Bottom, SocketSide::Top];
+        for &side in &sides {
+            // The distance from the centre of the side to the centre of the outermost sockets.
+            let extent = match side {
+                SocketSide::Left | SocketSide::Right => 19.0,
+                SocketSide::Bottom | SocketSide::Top => 39.0,
+            };
+            for &direction in &[Direction::Forwards, Direction::Backwards] {
+                let layout = SocketLayout::new(side, direction);
+                let point = |index, n| {
+                    socket_point(node_rect, border, socket_length, index, n, layout)
+                };
+
+                // Without sockets, no socket is positioned but the calculation remains finite.
+                let [x, y] = point(0, 0);
+                assert!(x.is_finite() && y.is_finite());
+
+                // A single socket is centred on its side.
+                assert_eq!(point(0, 1), expected_socket_point(side, 0.0));
+
+                // Two sockets lie at either end of the side in the layout's direction.
+                let first = match direction {
+                    Direction::Forwards => -extent,
+                    Direction::Backwards => extent,
+                };
+                assert_eq!(point(0, 2), expected_socket_point(side, first));
+                assert_eq!(point(1, 2), expected_socket_point(side, -first));
+            }
+        }
+    }
+
+    #[test]
+    fn socket_triangle_colors_match_socket_colors() {
+        let node_rect = Rect::from_xy_dim([0.0, 0.0], [100.0, 60.0]);
+        let colors = resolve_socket_colors(3, &[color::RED, color::GREEN], color::BLUE, None);
+        assert_eq!(colors, vec![color::RED, color::GREEN, color::BLUE]);
+        for &shape in &[SocketShape::Rectangle, SocketShape::Circle] {
+            let rects = SocketRects {
+                index: 0,
+                n_sockets: 3,
+                node_rect,
+                border: 6.0,
+                layout: SocketLayout::inputs_on_left(),
+                socket_length: 10.0,
+            };
+            let triangles = colored_socket_triangles(rects, &colors, shape, 10.0);
+            let per_socket = triangles.len() / colors.len();
+            assert_eq!(per_socket * colors.len(), triangles.len());
+            for (i, &Triangle(points)) in triangles.iter().enumerate() {
+                let expected: color::Rgba = colors[i / per_socket].into();
+                for &(_, rgba) in points.iter() {
+                    assert_eq!(rgba, expected);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Functions for triangulating thick, multi-segment edge paths.
+
+use conrod::{color, Point, Scalar};
+use conrod::position::Rect;
+use conrod::widget::primitive::shape::triangles::{ColoredPoint, Triangle};
+use std::f64::consts::PI;
+
+/// The ratio of miter length to half the path thickness beyond which a `Join::Miter` falls back
+/// to a bevel, avoiding long spikes at sharp corners.
+pub const MITER_LIMIT: Scalar = 4.0;
+/// The number of triangles used to describe a full circle for `Join::Round` corners.
+pub const ROUND_JOIN_RESOLUTION: usize = 32;
+
+/// Describes how the corners between two segments of a thick path are filled.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Join {
+    /// Extend the outer edges of both segments until they meet at a point.
+    ///
+    /// Falls back to `Bevel` for corners sharper than the `MITER_LIMIT` allows.
+    Miter,
+    /// Connect the outer edges of both segments with a straight line.
+    Bevel,
+    /// Connect the outer edges of both segments with a circular arc.
+    Round,
+}
+
+/// Produce the triangles describing a path through the given points with the given thickness.
+///
+/// Each segment is described by a rectangle of the given thickness and each corner is filled
+/// according to the given `join`. Consecutive duplicate points are ignored. Returns no triangles
+/// if fewer than two distinct points are given.
+pub fn triangles<I>(points: I, thickness: Scalar, join: Join) -> Vec<Triangle<Point>>
+where
+    I: IntoIterator<Item=Point>,
+{
+    let mut path: Vec<Point> = Vec::new();
+    for p in points {
+        match path.last() {
+            Some(&last) if magnitude(sub(p, last)) < ::std::f64::EPSILON => continue,
+            _ => path.push(p),
+        }
+    }
+
+    let half = thickness / 2.0;
+    let mut triangles = Vec::new();
+
+    // The rectangle for each segment.
+    for w in path.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let n = scale(normal(sub(b, a)), half);
+        let (a_l, a_r) = (add(a, n), sub(a, n));
+        let (b_l, b_r) = (add(b, n), sub(b, n));
+        triangles.push(Triangle([a_l, b_l, b_r]));
+        triangles.push(Triangle([a_l, b_r, a_r]));
+    }
+
+    // The join at each corner.
+    for w in path.windows(3) {
+        let (a, corner, b) = (w[0], w[1], w[2]);
+        let d0 = unit(sub(corner, a));
+        let d1 = unit(sub(b, corner));
+        let cross = d0[0] * d1[1] - d0[1] * d1[0];
+        if cross.abs() < ::std::f64::EPSILON {
+            continue;
+        }
+
+        // The gap to be filled is on the outside of the turn.
+        let side = if cross > 0.0 { -1.0 } else { 1.0 };
+        let out_a = scale(normal(d0), half * side);
+        let out_b = scale(normal(d1), half * side);
+
+        match join {
+            Join::Bevel => {
+                triangles.push(Triangle([corner, add(corner, out_a), add(corner, out_b)]));
+            },
+            Join::Miter => {
+                let bisector = add(out_a, out_b);
+                let bisector_len = magnitude(bisector);
+                // The miter length relative to `half` is `1 / cos(θ / 2)` where `θ` is the turn.
+                let cos_half_turn = bisector_len / (2.0 * half);
+                let miter_ratio = 1.0 / cos_half_turn;
+                if bisector_len < ::std::f64::EPSILON || miter_ratio > MITER_LIMIT {
+                    triangles.push(Triangle([corner, add(corner, out_a), add(corner, out_b)]));
+                } else {
+                    let miter = add(corner, scale(bisector, half * miter_ratio / bisector_len));
+                    triangles.push(Triangle([corner, add(corner, out_a), miter]));
+                    triangles.push(Triangle([corner, miter, add(corner, out_b)]));
+                }
+            },
+            Join::Round => {
+                let start = out_a[1].atan2(out_a[0]);
+                let mut sweep = out_b[1].atan2(out_b[0]) - start;
+                if sweep > PI {
+                    sweep -= 2.0 * PI;
+                } else if sweep < -PI {
+                    sweep += 2.0 * PI;
+                }
+                let steps = ((sweep.abs() / (2.0 * PI)) * ROUND_JOIN_RESOLUTION as Scalar)
+                    .ceil()
+                    .max(1.0) as usize;
+                let arc_point = |i: usize| {
+                    let angle = start + sweep * i as Scalar / steps as Scalar;
+                    [corner[0] + half * angle.cos(), corner[1] + half * angle.sin()]
+                };
+                for i in 0..steps {
+                    triangles.push(Triangle([corner, arc_point(i), arc_point(i + 1)]));
+                }
+            },
+        }
+    }
+
+    triangles
+}
+
+/// Produce the triangles describing a straight line from `start` to `end` with an arrowhead at
+/// `end` pointing along the direction of the line.
+///
+/// `arrow_size` is both the length and the width of the arrowhead. The line stops at the base of
+/// the arrowhead so that the two do not overlap. Returns no triangles if `start` and `end`
+/// coincide, as there is no direction in which the arrow could point.
+pub fn arrow_triangles(start: Point, end: Point, thickness: Scalar, arrow_size: Scalar)
+    -> Vec<Triangle<Point>>
+{
+    let delta = sub(end, start);
+    let length = magnitude(delta);
+    if length < ::std::f64::EPSILON {
+        return Vec::new();
+    }
+    let direction = scale(delta, 1.0 / length);
+    let arrow_length = arrow_size.min(length);
+    let base = sub(end, scale(direction, arrow_length));
+    let n = scale(normal(direction), arrow_size / 2.0);
+    let mut triangles = triangles(vec![start, base], thickness, Join::Miter);
+    triangles.push(Triangle([end, add(base, n), sub(base, n)]));
+    triangles
+}
+
+/// Produce the two triangles describing a straight line from `start` to `end` whose color fades
+/// from `start_color` to `end_color`.
+///
+/// The line is a quad of the given thickness centred on the segment. The colors are assigned to
+/// the vertices at either end, so that the color is interpolated along the length of the line but
+/// is constant across its width. Returns no triangles if `start` and `end` coincide.
+pub fn gradient_triangles(start: Point, end: Point, thickness: Scalar, start_color: color::Rgba,
+                          end_color: color::Rgba) -> Vec<Triangle<ColoredPoint>>
+{
+    if magnitude(sub(end, start)) < ::std::f64::EPSILON {
+        return Vec::new();
+    }
+    let n = scale(normal(sub(end, start)), thickness / 2.0);
+    let a = (add(start, n), start_color);
+    let b = (sub(start, n), start_color);
+    let c = (sub(end, n), end_color);
+    let d = (add(end, n), end_color);
+    vec![Triangle([a, b, c]), Triangle([a, c, d])]
+}
+
+/// Split the path through the given points into dashes of `dash_length` separated by gaps of
+/// `gap_length`.
+///
+/// Each dash is described by its own list of points, following any corners of the path that fall
+/// within it. The dash pattern continues across corners. If either length is not positive, the
+/// whole path is returned as a single dash.
+pub fn dashes<I>(points: I, dash_length: Scalar, gap_length: Scalar) -> Vec<Vec<Point>>
+where
+    I: IntoIterator<Item=Point>,
+{
+    let points: Vec<Point> = points.into_iter().collect();
+    if dash_length <= 0.0 || gap_length <= 0.0 {
+        return vec![points];
+    }
+
+    let mut dashes = Vec::new();
+    let mut dash = Vec::new();
+    // Whether we are currently within a dash (rather than a gap) and the length remaining of it.
+    let mut in_dash = true;
+    let mut remaining = dash_length;
+    for w in points.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let segment_length = magnitude(sub(b, a));
+        let mut travelled = 0.0;
+        if in_dash && dash.is_empty() {
+            dash.push(a);
+        }
+        while segment_length - travelled > remaining {
+            travelled += remaining;
+            let p = add(a, scale(sub(b, a), travelled / segment_length));
+            if in_dash {
+                dash.push(p);
+                dashes.push(::std::mem::replace(&mut dash, Vec::new()));
+                remaining = gap_length;
+            } else {
+                dash.push(p);
+                remaining = dash_length;
+            }
+            in_dash = !in_dash;
+        }
+        remaining -= segment_length - travelled;
+        if in_dash {
+            dash.push(b);
+        }
+    }
+    if dash.len() > 1 {
+        dashes.push(dash);
+    }
+    dashes
+}
+
+/// Sample the cubic Bézier curve described by the given start, control and end points.
+///
+/// Produces `segments + 1` points from `start` to `end` inclusive. At least one segment is always
+/// produced.
+pub fn cubic_bezier(start: Point, ctrl_a: Point, ctrl_b: Point, end: Point, segments: usize)
+    -> Vec<Point>
+{
+    let segments = ::std::cmp::max(segments, 1);
+    (0..segments + 1)
+        .map(|i| {
+            let t = i as Scalar / segments as Scalar;
+            let u = 1.0 - t;
+            let (w0, w1, w2, w3) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            [
+                w0 * start[0] + w1 * ctrl_a[0] + w2 * ctrl_b[0] + w3 * end[0],
+                w0 * start[1] + w1 * ctrl_a[1] + w2 * ctrl_b[1] + w3 * end[1],
+            ]
+        })
+        .collect()
+}
+
+/// The smallest `Rect` containing all of the given triangles.
+///
+/// Returns `None` if there are no triangles.
+pub fn bounding_rect(triangles: &[Triangle<Point>]) -> Option<Rect> {
+    let mut points = triangles.iter().flat_map(|&Triangle(ps)| ps.to_vec());
+    let first = match points.next() {
+        None => return None,
+        Some(p) => p,
+    };
+    let (min, max) = points.fold((first, first), |(min, max), p| {
+        ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+    });
+    Some(Rect::from_corners(min, max))
+}
+
+fn add(a: Point, b: Point) -> Point {
+    [a[0] + b[0], a[1] + b[1]]
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    [a[0] - b[0], a[1] - b[1]]
+}
+
+fn scale(v: Point, s: Scalar) -> Point {
+    [v[0] * s, v[1] * s]
+}
+
+fn magnitude(v: Point) -> Scalar {
+    (v[0] * v[0] + v[1] * v[1]).sqrt()
+}
+
+fn unit(v: Point) -> Point {
+    scale(v, 1.0 / magnitude(v))
+}
+
+// The unit vector perpendicular (counter-clockwise) to the given direction.
+fn normal(direction: Point) -> Point {
+    let d = unit(direction);
+    [-d[1], d[0]]
+}
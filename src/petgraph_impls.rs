@@ -0,0 +1,162 @@
+//! Conveniences for instantiating a **Graph** widget from a `petgraph::Graph` or a
+//! `petgraph::stable_graph::StableGraph`.
+//!
+//! Only available when the `petgraph` feature is enabled.
+
+use petgraph::{self, graph, stable_graph};
+use petgraph::visit::EdgeRef;
+use std::slice;
+use super::{Graph, Layout, NodeSocket};
+
+/// Edge weights from which the output and input socket indices of an edge may be derived.
+///
+/// Implemented for `(usize, usize)` (interpreted as `(output, input)`) and for `()`, in which case
+/// both socket indices default to `0`.
+pub trait EdgeSockets {
+    /// The index of the output socket on the start node and the index of the input socket on the
+    /// end node.
+    fn socket_indices(&self) -> (usize, usize);
+}
+
+/// A function producing the output and input socket indices of an edge from its weight.
+pub type SocketIndices<E> = fn(&E) -> (usize, usize);
+
+/// The node iterator used by a **Graph** produced via `Graph::from_graph`.
+pub type Nodes<Ix> = graph::NodeIndices<Ix>;
+
+/// A socket on a node within a `petgraph::Graph`.
+pub type Socket<Ix> = NodeSocket<graph::NodeIndex<Ix>>;
+
+/// The node iterator used by a **Graph** produced via `Graph::from_stable_graph`.
+pub type StableNodes<'a, N, Ix> = stable_graph::NodeIndices<'a, N, Ix>;
+
+/// The edge iterator used by a **Graph** produced via `Graph::from_graph`.
+///
+/// Yields each edge of the `petgraph::Graph` as a `(NodeSocket, NodeSocket)` pair, with the socket
+/// indices derived from the edge's weight.
+pub struct GraphEdges<'a, E: 'a, Ix: 'a> {
+    edges: slice::Iter<'a, graph::Edge<E, Ix>>,
+    socket_indices: SocketIndices<E>,
+}
+
+/// The edge iterator used by a **Graph** produced via `Graph::from_stable_graph`.
+///
+/// Yields each edge of the `StableGraph` as a `(NodeSocket, NodeSocket)` pair, with the socket
+/// indices derived from the edge's weight.
+pub struct StableGraphEdges<'a, E: 'a, Ix: 'a> {
+    edges: stable_graph::EdgeReferences<'a, E, Ix>,
+    socket_indices: SocketIndices<E>,
+}
+
+impl EdgeSockets for (usize, usize) {
+    fn socket_indices(&self) -> (usize, usize) {
+        *self
+    }
+}
+
+impl EdgeSockets for () {
+    fn socket_indices(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+impl<'a, E, Ix> Graph<'a, Nodes<Ix>, GraphEdges<'a, E, Ix>>
+where
+    Ix: graph::IndexType + Send,
+{
+    /// Begin building a new **Graph** widget for the given `petgraph::Graph`.
+    ///
+    /// A node is produced for each node in the graph and an edge for each edge, with the socket
+    /// indices of each edge derived from its weight via `EdgeSockets`.
+    pub fn from_graph<N, Ty>(
+        graph: &'a petgraph::Graph<N, E, Ty, Ix>,
+        layout: &'a Layout<graph::NodeIndex<Ix>>,
+    ) -> Self
+    where
+        E: EdgeSockets,
+        Ty: petgraph::EdgeType,
+    {
+        Self::from_graph_with(graph, layout, E::socket_indices)
+    }
+
+    /// Begin building a new **Graph** widget for the given `petgraph::Graph`, deriving the
+    /// output and input socket indices of each edge from its weight via `socket_indices`.
+    ///
+    /// This is useful for edge weights that do not implement `EdgeSockets`.
+    pub fn from_graph_with<N, Ty>(
+        graph: &'a petgraph::Graph<N, E, Ty, Ix>,
+        layout: &'a Layout<graph::NodeIndex<Ix>>,
+        socket_indices: SocketIndices<E>,
+    ) -> Self
+    where
+        Ty: petgraph::EdgeType,
+    {
+        let edges = GraphEdges { edges: graph.raw_edges().iter(), socket_indices };
+        Graph::new(graph.node_indices(), edges, layout)
+    }
+}
+
+impl<'a, N, E, Ix> Graph<'a, StableNodes<'a, N, Ix>, StableGraphEdges<'a, E, Ix>>
+where
+    Ix: graph::IndexType + Send,
+{
+    /// Begin building a new **Graph** widget for the given `StableGraph`.
+    ///
+    /// A node is produced for each occupied node index and an edge for each occupied edge index,
+    /// so indices left vacant by removals are skipped. As with `Graph::from_graph`, the socket
+    /// indices of each edge are derived from its weight via `EdgeSockets`.
+    pub fn from_stable_graph<Ty>(
+        graph: &'a stable_graph::StableGraph<N, E, Ty, Ix>,
+        layout: &'a Layout<graph::NodeIndex<Ix>>,
+    ) -> Self
+    where
+        E: EdgeSockets,
+        Ty: petgraph::EdgeType,
+    {
+        Self::from_stable_graph_with(graph, layout, E::socket_indices)
+    }
+
+    /// Begin building a new **Graph** widget for the given `StableGraph`, deriving the output and
+    /// input socket indices of each edge from its weight via `socket_indices`.
+    pub fn from_stable_graph_with<Ty>(
+        graph: &'a stable_graph::StableGraph<N, E, Ty, Ix>,
+        layout: &'a Layout<graph::NodeIndex<Ix>>,
+        socket_indices: SocketIndices<E>,
+    ) -> Self
+    where
+        Ty: petgraph::EdgeType,
+    {
+        let edges = StableGraphEdges { edges: graph.edge_references(), socket_indices };
+        Graph::new(graph.node_indices(), edges, layout)
+    }
+}
+
+impl<'a, E, Ix> Iterator for GraphEdges<'a, E, Ix>
+where
+    Ix: graph::IndexType,
+{
+    type Item = (Socket<Ix>, Socket<Ix>);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.edges.next().map(|edge| {
+            let (output, input) = (self.socket_indices)(&edge.weight);
+            let start = NodeSocket { id: edge.source(), socket_index: output };
+            let end = NodeSocket { id: edge.target(), socket_index: input };
+            (start, end)
+        })
+    }
+}
+
+impl<'a, E, Ix> Iterator for StableGraphEdges<'a, E, Ix>
+where
+    Ix: graph::IndexType,
+{
+    type Item = (Socket<Ix>, Socket<Ix>);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.edges.next().map(|edge| {
+            let (output, input) = (self.socket_indices)(edge.weight());
+            let start = NodeSocket { id: edge.source(), socket_index: output };
+            let end = NodeSocket { id: edge.target(), socket_index: input };
+            (start, end)
+        })
+    }
+}
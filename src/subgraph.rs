@@ -0,0 +1,92 @@
+//! A snapshot of a selection of nodes and the edges between them, e.g. for copying and pasting.
+//!
+//! A `Subgraph` is plain data. It is produced from a selection of nodes via `Subgraph::new` or
+//! `Session::subgraph`, while pasting it into the user's graph is left to the user, who is
+//! responsible for minting the new node identifiers.
+
+use conrod::Point;
+use std::collections::{HashMap, HashSet};
+use super::{Layout, NodeId, NodeSocket};
+
+/// A selection of nodes along with their positions and the edges wholly contained within the
+/// selection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subgraph<NI> {
+    /// Each selected node along with its position in the order in which it was selected.
+    ///
+    /// Nodes without a position in the `Layout` are positioned at the centre of the graph.
+    pub nodes: Vec<(NI, Point)>,
+    /// Each edge whose start and end nodes are both selected.
+    pub edges: Vec<(NodeSocket<NI>, NodeSocket<NI>)>,
+}
+
+impl<NI> Subgraph<NI>
+where
+    NI: NodeId,
+{
+    /// Capture the given selection of nodes along with their positions within the `layout`.
+    ///
+    /// Only the `edges` whose start and end nodes are both within the selection are kept.
+    /// Duplicate nodes within the selection are ignored.
+    pub fn new<S, E>(selection: S, edges: E, layout: &Layout<NI>) -> Self
+    where
+        S: IntoIterator<Item=NI>,
+        E: IntoIterator<Item=(NodeSocket<NI>, NodeSocket<NI>)>,
+    {
+        let mut selected = HashSet::new();
+        let nodes = selection
+            .into_iter()
+            .filter(|&node_id| selected.insert(node_id))
+            .map(|node_id| (node_id, layout.get(&node_id).cloned().unwrap_or([0.0; 2])))
+            .collect();
+        let edges = edges
+            .into_iter()
+            .filter(|&(start, end)| selected.contains(&start.id) && selected.contains(&end.id))
+            .collect();
+        Subgraph { nodes, edges }
+    }
+
+    /// Whether or not the subgraph contains no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Shift the position of every node by the given offset, e.g. so that pasted nodes do not
+    /// lie directly over the nodes from which they were copied.
+    pub fn offset(mut self, offset: Point) -> Self {
+        for &mut (_, ref mut point) in &mut self.nodes {
+            point[0] += offset[0];
+            point[1] += offset[1];
+        }
+        self
+    }
+
+    /// Remap the identifier of every node, producing the subgraph to be pasted.
+    ///
+    /// `new_id` is called exactly once for each node in the order in which the nodes were
+    /// selected, so that it may mint a fresh identifier (e.g. by adding a node to the user's
+    /// graph) for each. The start and end of every edge are then remapped to the new identifiers
+    /// of their nodes, while their socket indices remain unchanged.
+    pub fn map_ids<F, NJ>(self, mut new_id: F) -> Subgraph<NJ>
+    where
+        F: FnMut(NI) -> NJ,
+        NJ: NodeId,
+    {
+        let Subgraph { nodes, edges } = self;
+        let ids: HashMap<NI, NJ> = nodes.iter().map(|&(id, _)| (id, new_id(id))).collect();
+        let nodes = nodes.into_iter().map(|(id, point)| (ids[&id], point)).collect();
+        let socket = |socket: NodeSocket<NI>| {
+            NodeSocket { id: ids[&socket.id], socket_index: socket.socket_index }
+        };
+        let edges = edges.into_iter().map(|(start, end)| (socket(start), socket(end))).collect();
+        Subgraph { nodes, edges }
+    }
+
+    /// Insert the position of every node into the given `Layout`.
+    ///
+    /// This is typically called after `map_ids` so that the pasted nodes do not replace the
+    /// positions of the nodes from which they were copied.
+    pub fn extend_layout(&self, layout: &mut Layout<NI>) {
+        layout.extend(self.nodes.iter().cloned());
+    }
+}